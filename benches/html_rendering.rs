@@ -29,7 +29,7 @@ fn criterion_benchmark(c: &mut Criterion) {
 
         b.iter(|| {
             buf.clear();
-            html::push_html(&mut buf, events.clone().into_iter());
+            html::push_html(&mut buf, events.clone());
         })
     });
 
@@ -35,6 +35,21 @@ mod to_html {
         group.finish();
     }
 
+    pub fn pathological_codeblocks_same_length(c: &mut Criterion) {
+        let mut group = c.benchmark_group("pathological_codeblocks_same_length");
+        for i in 1..6 {
+            // An unmatched opener followed by many runs of another length, so
+            // all of them are queued in `CodeDelims` before being paired up.
+            let mut buf = String::from("``` ");
+            buf.push_str(&"`` a ".repeat(i * 2000));
+            group.throughput(Throughput::Bytes(buf.len() as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(i), &buf, |b, buf| {
+                b.iter(|| render_html(buf, Options::empty()));
+            });
+        }
+        group.finish();
+    }
+
     fn render_html(text: &str, opts: Options) -> String {
         let mut s = String::with_capacity(text.len() * 3 / 2);
         let p = Parser::new_ext(text, opts);
@@ -46,6 +61,7 @@ mod to_html {
 criterion_group!(
     benches,
     to_html::pathological_codeblocks1,
-    to_html::advanced_pathological_codeblocks
+    to_html::advanced_pathological_codeblocks,
+    to_html::pathological_codeblocks_same_length
 );
 criterion_main!(benches);
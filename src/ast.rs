@@ -0,0 +1,72 @@
+//! Nested document tree built from the flat event stream, for serializing a
+//! parsed document as a whole, for example to JSON.
+
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Event, Tag};
+
+/// A node of the document tree.
+///
+/// Nodes serialize without a variant name: an element becomes an object with
+/// `tag` and `children` fields, and a leaf serializes like the event it holds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Node<'a> {
+    /// An element, built from a `Start` event, the events up to its matching
+    /// `End` event, and that `End` event.
+    Element {
+        #[serde(borrow)]
+        tag: Tag<'a>,
+        children: Vec<Node<'a>>,
+    },
+    /// Any event other than `Start` and `End`.
+    #[serde(borrow)]
+    Leaf(Event<'a>),
+}
+
+/// Builds the document tree from an event stream, returning the top level
+/// nodes. Elements still open when the events run out are closed, and `End`
+/// events without a matching `Start` are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{ast, Parser};
+///
+/// let tree = ast::from_events(Parser::new("*hi*"));
+/// let json = serde_json::to_string(&tree).unwrap();
+///
+/// assert_eq!(
+///     json,
+///     r#"[{"tag":"Paragraph","children":[{"tag":"Emphasis","children":[{"Text":"hi"}]}]}]"#
+/// );
+/// ```
+pub fn from_events<'a, I>(iter: I) -> Vec<Node<'a>>
+where
+    I: IntoIterator<Item = Event<'a>>,
+{
+    // Open elements, each with the children of its parent collected so far.
+    let mut stack: Vec<(Tag<'a>, Vec<Node<'a>>)> = Vec::new();
+    let mut nodes = Vec::new();
+
+    for event in iter {
+        match event {
+            Event::Start(tag) => stack.push((tag, mem::take(&mut nodes))),
+            Event::End(_) => {
+                if let Some((tag, parent)) = stack.pop() {
+                    let children = mem::replace(&mut nodes, parent);
+                    nodes.push(Node::Element { tag, children });
+                }
+            }
+            event => nodes.push(Node::Leaf(event)),
+        }
+    }
+    while let Some((tag, parent)) = stack.pop() {
+        let children = mem::replace(&mut nodes, parent);
+        nodes.push(Node::Element { tag, children });
+    }
+
+    nodes
+}
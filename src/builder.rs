@@ -0,0 +1,160 @@
+//! Programmatic construction of event streams.
+
+use crate::{CowStr, Event, Tag};
+
+/// Builds a sequence of events, keeping track of the open tags so that every
+/// `Start` event gets a matching `End` event. Events from other sources, such
+/// as a [`Parser`](struct.Parser.html), can be spliced in with [`event`](#method.event)
+/// or [`Extend::extend`].
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{html, EventBuilder, Parser, Tag};
+///
+/// let mut builder = EventBuilder::new();
+/// builder.start(Tag::BlockQuote);
+/// builder.extend(Parser::new("*included*"));
+/// builder.end();
+///
+/// let mut html_buf = String::new();
+/// html::push_html(&mut html_buf, builder.finish());
+/// assert_eq!(html_buf, "<blockquote>\n<p><em>included</em></p>\n</blockquote>\n");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EventBuilder<'a> {
+    events: Vec<Event<'a>>,
+    open: Vec<Tag<'a>>,
+}
+
+impl<'a> EventBuilder<'a> {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a `Start` event for the tag.
+    pub fn start(&mut self, tag: Tag<'a>) -> &mut Self {
+        self.open.push(tag.clone());
+        self.events.push(Event::Start(tag));
+        self
+    }
+
+    /// Adds the `End` event for the most recently started tag that is still
+    /// open.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no open tag.
+    pub fn end(&mut self) -> &mut Self {
+        let tag = self.open.pop().expect("no open tag to end");
+        self.events.push(Event::End(tag));
+        self
+    }
+
+    /// Adds a text event.
+    pub fn text(&mut self, text: impl Into<CowStr<'a>>) -> &mut Self {
+        self.events.push(Event::Text(text.into()));
+        self
+    }
+
+    /// Adds an inline code event.
+    pub fn code(&mut self, code: impl Into<CowStr<'a>>) -> &mut Self {
+        self.events.push(Event::Code(code.into()));
+        self
+    }
+
+    /// Adds any event. `Start` and `End` events are tracked like those added
+    /// by [`start`](#method.start) and [`end`](#method.end). In debug builds,
+    /// this panics if an `End` event doesn't match the open tag.
+    pub fn event(&mut self, event: Event<'a>) -> &mut Self {
+        match event {
+            Event::Start(ref tag) => self.open.push(tag.clone()),
+            Event::End(ref tag) => {
+                let open = self.open.pop();
+                debug_assert_eq!(open.as_ref(), Some(tag), "unbalanced end event");
+            }
+            _ => {}
+        }
+        self.events.push(event);
+        self
+    }
+
+    /// Returns the number of tags that have been started but not ended.
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Returns the events. In debug builds, this panics if any tags are
+    /// still open.
+    pub fn finish(self) -> Vec<Event<'a>> {
+        debug_assert!(self.open.is_empty(), "unclosed tags: {:?}", self.open);
+        self.events
+    }
+}
+
+impl<'a> Extend<Event<'a>> for EventBuilder<'a> {
+    fn extend<I: IntoIterator<Item = Event<'a>>>(&mut self, iter: I) {
+        for event in iter {
+            self.event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EventBuilder;
+    use crate::{html, Event, Parser, Tag};
+
+    #[test]
+    fn balanced() {
+        let mut builder = EventBuilder::new();
+        builder
+            .start(Tag::Paragraph)
+            .text("a ")
+            .start(Tag::Emphasis)
+            .code("b")
+            .end()
+            .end();
+        assert_eq!(0, builder.depth());
+
+        let mut s = String::new();
+        html::push_html(&mut s, builder.finish());
+        assert_eq!("<p>a <em><code>b</code></em></p>\n", s);
+    }
+
+    #[test]
+    fn splice_parser_output() {
+        let mut builder = EventBuilder::new();
+        builder.start(Tag::Item);
+        builder.extend(Parser::new("# a"));
+        assert_eq!(1, builder.depth());
+        builder.end();
+        let events = builder.finish();
+        assert_eq!(Event::End(Tag::Item), events[4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no open tag to end")]
+    fn end_without_start() {
+        EventBuilder::new().text("a").end();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "unclosed tags")]
+    fn finish_with_open_tag() {
+        let mut builder = EventBuilder::new();
+        builder.start(Tag::Paragraph).text("a");
+        builder.finish();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "unbalanced end event")]
+    fn mismatched_end_event() {
+        let mut builder = EventBuilder::new();
+        builder.start(Tag::Paragraph);
+        builder.event(Event::End(Tag::Emphasis));
+    }
+}
@@ -0,0 +1,129 @@
+//! Tracking of the tags enclosing each event.
+
+use crate::{Event, Tag};
+
+/// Wraps an event iterator to keep track of the tags that are open, so that
+/// the context of an event, such as being inside a block quote or a table,
+/// is known without keeping a stack by hand.
+///
+/// After an event is returned by [`next`](#method.next), [`context`](#method.context)
+/// gives the tags enclosing it, from the outermost inwards. The tag of a
+/// `Start` or `End` event is not part of its own context.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{ContextIter, Event, Parser, Tag};
+///
+/// let mut iter = ContextIter::new(Parser::new("> *quoted*"));
+/// while let Some(event) = iter.next() {
+///     if let Event::Text(text) = event {
+///         assert_eq!(&*text, "quoted");
+///         assert_eq!(
+///             iter.context(),
+///             &[Tag::BlockQuote, Tag::Paragraph, Tag::Emphasis][..]
+///         );
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ContextIter<'a, I> {
+    iter: I,
+    open: Vec<Tag<'a>>,
+    /// Tag of the last event returned if it was a `Start` event. It only
+    /// becomes part of the context for the events after it.
+    started: Option<Tag<'a>>,
+}
+
+impl<'a, I> ContextIter<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    /// Wraps an event iterator, starting with no tags open.
+    pub fn new<T>(iter: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        Self {
+            iter: iter.into_iter(),
+            open: Vec::new(),
+            started: None,
+        }
+    }
+
+    /// Returns the tags enclosing the last event returned, from the outermost
+    /// inwards.
+    pub fn context(&self) -> &[Tag<'a>] {
+        &self.open
+    }
+}
+
+impl<'a, I> Iterator for ContextIter<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        if let Some(tag) = self.started.take() {
+            self.open.push(tag);
+        }
+        let event = self.iter.next()?;
+        match event {
+            Event::Start(ref tag) => self.started = Some(tag.clone()),
+            Event::End(_) => {
+                self.open.pop();
+            }
+            _ => {}
+        }
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ContextIter;
+    use crate::{Event, Parser, Tag};
+
+    #[test]
+    fn nested_blockquote_and_list() {
+        let mut iter = ContextIter::new(Parser::new("> - a\n>   > b\n"));
+        let mut contexts = Vec::new();
+        while let Some(event) = iter.next() {
+            if let Event::Text(text) = event {
+                contexts.push((text, iter.context().to_vec()));
+            }
+        }
+        assert_eq!(
+            vec![
+                (
+                    "a".into(),
+                    vec![Tag::BlockQuote, Tag::List(None), Tag::Item]
+                ),
+                (
+                    "b".into(),
+                    vec![
+                        Tag::BlockQuote,
+                        Tag::List(None),
+                        Tag::Item,
+                        Tag::BlockQuote,
+                        Tag::Paragraph
+                    ]
+                ),
+            ],
+            contexts
+        );
+    }
+
+    #[test]
+    fn start_and_end_are_outside_their_tag() {
+        let mut iter = ContextIter::new(Parser::new("*a*"));
+        let mut contexts = Vec::new();
+        while iter.next().is_some() {
+            contexts.push(iter.context().len());
+        }
+        // paragraph start, emphasis start, text, emphasis end, paragraph end
+        assert_eq!(vec![0, 1, 2, 1, 0], contexts);
+        assert!(iter.context().is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+//! Readable dumps of event streams, for debugging and issue reports.
+
+use std::fmt::Write;
+
+use crate::Event;
+
+/// Renders the events one per line, using their `Debug` representation. The
+/// events between a `Start` event and its `End` event are indented by two
+/// spaces per level.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{debug::events_to_debug_string, Parser};
+///
+/// assert_eq!(
+///     events_to_debug_string(Parser::new("*a*")),
+///     "Start(Paragraph)\n  Start(Emphasis)\n    Text(Borrowed(\"a\"))\n  End(Emphasis)\nEnd(Paragraph)\n"
+/// );
+/// ```
+pub fn events_to_debug_string<'a, I>(events: I) -> String
+where
+    I: IntoIterator<Item = Event<'a>>,
+{
+    let mut s = String::new();
+    let mut depth = 0usize;
+    for event in events {
+        if let Event::End(_) = event {
+            depth = depth.saturating_sub(1);
+        }
+        for _ in 0..depth {
+            s.push_str("  ");
+        }
+        writeln!(s, "{:?}", event).unwrap();
+        if let Event::Start(_) = event {
+            depth += 1;
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::events_to_debug_string;
+    use crate::Parser;
+
+    #[test]
+    fn nested_document() {
+        let s = events_to_debug_string(Parser::new("> - a `b`\n\nc\n"));
+        assert_eq!(
+            s,
+            r#"Start(BlockQuote)
+  Start(List(None))
+    Start(Item)
+      Text(Borrowed("a "))
+      Code(Borrowed("b"))
+    End(Item)
+  End(List(None))
+End(BlockQuote)
+Start(Paragraph)
+  Text(Borrowed("c"))
+End(Paragraph)
+"#
+        );
+    }
+}
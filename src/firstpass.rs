@@ -3,25 +3,36 @@
 
 use std::cmp::max;
 
-use crate::parse::{scan_containers, Allocations, Item, ItemBody, LinkDef};
+use crate::parse::{
+    scan_containers, Allocations, HeadingAttributes, HeadingIndex, Item, ItemBody, LinkDef,
+    ParserConfig,
+};
 use crate::scanners::*;
 use crate::strings::CowStr;
 use crate::tree::{Tree, TreeIndex};
 use crate::Options;
 use crate::{
     linklabel::{scan_link_label_rest, LinkLabel},
-    HeadingLevel,
+    HeadingLevel, LinkType,
 };
 
-use unicase::UniCase;
-
 /// Runs the first pass, which resolves the block structure of the document,
 /// and returns the resulting tree.
-pub(crate) fn run_first_pass<'a>(text: &'a str, options: Options) -> (Tree<Item>, Allocations<'a>) {
+pub(crate) fn run_first_pass<'a>(
+    text: &'a str,
+    options: Options,
+    config: &ParserConfig,
+) -> (Tree<Item>, Allocations<'a>) {
     // This is a very naive heuristic for the number of nodes
     // we'll need.
-    let start_capacity = max(128, text.len() / 32);
-    let lookup_table = &create_lut(&options);
+    let start_capacity = config
+        .initial_tree_capacity
+        .unwrap_or_else(|| max(128, text.len() / 32));
+    let extra_emphasis = config
+        .extra_emphasis_delimiter
+        .filter(|&c| c.is_ascii_punctuation() && !"*_~`&\\[]<!|.-\"'".contains(c))
+        .map(|c| c as u8);
+    let lookup_table = &create_lut(&options, extra_emphasis);
     let first_pass = FirstPass {
         text,
         tree: Tree::with_capacity(start_capacity),
@@ -29,6 +40,9 @@ pub(crate) fn run_first_pass<'a>(text: &'a str, options: Options) -> (Tree<Item>
         last_line_blank: false,
         allocs: Allocations::new(),
         options,
+        hrule_min: config.thematic_break_min,
+        max_refdefs: config.max_reference_definitions,
+        extra_emphasis,
         list_nesting: 0,
         lookup_table,
     };
@@ -43,6 +57,12 @@ struct FirstPass<'a, 'b> {
     last_line_blank: bool,
     allocs: Allocations<'a>,
     options: Options,
+    /// Minimum number of characters in a thematic break.
+    hrule_min: usize,
+    /// Maximum number of reference definitions to register.
+    max_refdefs: Option<usize>,
+    /// Delimiter character of extra emphasis spans, if any.
+    extra_emphasis: Option<u8>,
     list_nesting: usize,
     lookup_table: &'b LookupTable,
 }
@@ -93,7 +113,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         // Process new containers
         loop {
             let container_start = start_ix + line_start.bytes_scanned();
-            if let Some((ch, index, indent)) = line_start.scan_list_marker() {
+            if let Some((ch, index, indent)) = line_start.scan_list_marker(self.hrule_min) {
                 let after_marker_index = start_ix + line_start.bytes_scanned();
                 self.continue_list(container_start, ch, index);
                 self.tree.append(Item {
@@ -181,16 +201,23 @@ impl<'a, 'b> FirstPass<'a, 'b> {
             }
         }
 
-        if let Ok(n) = scan_hrule(&bytes[ix..]) {
+        if let Ok(n) = scan_hrule(&bytes[ix..], self.hrule_min) {
             return self.parse_hrule(n, ix);
         }
 
-        if let Some(atx_size) = scan_atx_heading(&bytes[ix..]) {
+        if let Some(atx_size) = scan_allowed_atx_heading(&bytes[ix..], self.options) {
             return self.parse_atx_heading(ix, atx_size);
         }
 
         // parse refdef
-        if let Some((bytecount, label, link_def)) = self.parse_refdef_total(ix) {
+        let refdefs_full =
+            matches!(self.max_refdefs, Some(max) if self.allocs.refdefs.0.len() >= max);
+        let refdef = if refdefs_full {
+            None
+        } else {
+            self.parse_refdef_total(ix)
+        };
+        if let Some((bytecount, label, link_def)) = refdef {
             self.allocs.refdefs.0.entry(label).or_insert(link_def);
             let ix = ix + bytecount;
             // try to read trailing whitespace or it will register as a completely blank line
@@ -198,7 +225,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
             return ix + scan_blank_line(&bytes[ix..]).unwrap_or(0);
         }
 
-        if let Some((n, fence_ch)) = scan_code_fence(&bytes[ix..]) {
+        if let Some((n, fence_ch)) = scan_allowed_code_fence(&bytes[ix..], self.options) {
             return self.parse_fenced_code_block(ix, indent, fence_ch, n);
         }
         self.parse_paragraph(ix)
@@ -252,7 +279,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
                 body: ItemBody::TableCell,
             });
             self.tree.push();
-            let (next_ix, _brk) = self.parse_line(ix, TableParseMode::Active);
+            let (next_ix, _brk) = self.parse_line(ix, None, TableParseMode::Active);
 
             if let Some(cur_ix) = self.tree.cur() {
                 let trailing_whitespace = scan_rev_while(&bytes[..next_ix], is_ascii_whitespace);
@@ -301,7 +328,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         }
         line_start.scan_all_space();
         ix += line_start.bytes_scanned();
-        if scan_paragraph_interrupt(&bytes[ix..]) {
+        if scan_paragraph_interrupt(&bytes[ix..], self.options, self.hrule_min) {
             return None;
         }
 
@@ -311,22 +338,25 @@ impl<'a, 'b> FirstPass<'a, 'b> {
 
     /// Returns offset of line start after paragraph.
     fn parse_paragraph(&mut self, start_ix: usize) -> usize {
-        let node_ix = self.tree.append(Item {
+        let prev_ix = self.tree.cur();
+        let mut node_ix = self.tree.append(Item {
             start: start_ix,
             end: 0, // will get set later
             body: ItemBody::Paragraph,
         });
         self.tree.push();
         let bytes = self.text.as_bytes();
+        // where the current line starts, if it isn't the first one
+        let mut line_split = None;
 
         let mut ix = start_ix;
-        loop {
+        let last_brk = loop {
             let scan_mode = if self.options.contains(Options::ENABLE_TABLES) && ix == start_ix {
                 TableParseMode::Scan
             } else {
                 TableParseMode::Disabled
             };
-            let (next_ix, brk) = self.parse_line(ix, scan_mode);
+            let (next_ix, brk) = self.parse_line(ix, None, scan_mode);
 
             // break out when we find a table
             if let Some(Item {
@@ -350,46 +380,129 @@ impl<'a, 'b> FirstPass<'a, 'b> {
             if !line_start.scan_space(4) {
                 let ix_new = ix + line_start.bytes_scanned();
                 if n_containers == self.tree.spine_len() {
-                    if let Some(ix_setext) = self.parse_setext_heading(ix_new, node_ix) {
-                        if let Some(Item {
-                            start,
-                            body: ItemBody::HardBreak,
-                            ..
-                        }) = brk
-                        {
-                            if bytes[start] == b'\\' {
-                                self.tree.append_text(start, start + 1);
-                            }
+                    if self
+                        .options
+                        .contains(Options::ENABLE_SINGLE_LINE_SETEXT_HEADINGS)
+                        && scan_setext_heading(&bytes[ix_new..]).is_some()
+                    {
+                        if let Some(split) = line_split {
+                            node_ix = self.split_off_last_line(split);
                         }
+                    }
+                    if let Some(ix_setext) = self.parse_setext_heading(ix_new, node_ix) {
                         ix = ix_setext;
-                        break;
+                        break brk;
                     }
                 }
                 // first check for non-empty lists, then for other interrupts
                 let suffix = &bytes[ix_new..];
-                if self.interrupt_paragraph_by_list(suffix) || scan_paragraph_interrupt(suffix) {
-                    break;
+                if self.interrupt_paragraph_by_list(suffix)
+                    || scan_paragraph_interrupt(suffix, self.options, self.hrule_min)
+                {
+                    break brk;
                 }
             }
             line_start.scan_all_space();
             if line_start.is_at_eol() {
-                break;
+                break brk;
             }
             ix = next_ix + line_start.bytes_scanned();
+            let text_end = self.tree.cur();
             if let Some(item) = brk {
-                self.tree.append(item);
+                let is_continuation = self.options.contains(Options::ENABLE_LINE_CONTINUATIONS)
+                    && item.body == ItemBody::HardBreak
+                    && bytes[item.start] == b'\\';
+                if !is_continuation {
+                    self.tree.append(item);
+                }
+            }
+            if let (Some(text_end), Some(line_prev)) = (text_end, self.tree.cur()) {
+                line_split = Some(LineSplit {
+                    text_end,
+                    line_prev,
+                    line_begin: next_ix,
+                    content_start: ix,
+                });
+            }
+        };
+
+        // a backslash at the end of the paragraph is not a hard break
+        if let Some(Item {
+            start,
+            body: ItemBody::HardBreak,
+            ..
+        }) = last_brk
+        {
+            if bytes[start] == b'\\' {
+                self.tree.append_text(start, start + 1);
             }
         }
 
         self.pop(ix);
+        if self.options.contains(Options::OMIT_BLANK_PARAGRAPHS) && self.is_blank_paragraph(node_ix)
+        {
+            self.tree.remove_cur(prev_ix);
+        }
         ix
     }
 
+    /// Ends the paragraph being parsed before its last line, and moves the last
+    /// line into a new paragraph after it. Returns the new paragraph.
+    fn split_off_last_line(&mut self, split: LineSplit) -> TreeIndex {
+        let first_ix = self.tree[split.line_prev].next;
+        self.tree[split.text_end].next = None;
+        self.pop(split.line_begin);
+        let node_ix = self.tree.append(Item {
+            start: split.content_start,
+            end: 0, // will get set later
+            body: ItemBody::Paragraph,
+        });
+        self.tree[node_ix].child = first_ix;
+        self.tree.push();
+        // move on to the last node of the line, so that parsing continues after it
+        let mut cur = self.tree.cur();
+        while let Some(cur_ix) = cur {
+            if self.tree[cur_ix].next.is_none() {
+                break;
+            }
+            cur = self.tree.next_sibling(cur_ix);
+        }
+        node_ix
+    }
+
+    /// Checks whether a paragraph consists of only whitespace and line breaks.
+    fn is_blank_paragraph(&self, node_ix: TreeIndex) -> bool {
+        if self.tree[node_ix].item.body != ItemBody::Paragraph {
+            return false;
+        }
+        let mut child = self.tree[node_ix].child;
+        while let Some(child_ix) = child {
+            let item = self.tree[child_ix].item;
+            let blank = match item.body {
+                ItemBody::Text => self.text[item.start..item.end]
+                    .chars()
+                    .all(char::is_whitespace),
+                ItemBody::SoftBreak | ItemBody::HardBreak => true,
+                _ => false,
+            };
+            if !blank {
+                return false;
+            }
+            child = self.tree[child_ix].next;
+        }
+        true
+    }
+
     /// Returns end ix of setext_heading on success.
     fn parse_setext_heading(&mut self, ix: usize, node_ix: TreeIndex) -> Option<usize> {
         let bytes = self.text.as_bytes();
         let (n, level) = scan_setext_heading(&bytes[ix..])?;
-        self.tree[node_ix].item.body = ItemBody::Heading(level);
+        let attrs = if self.options.contains(Options::ENABLE_HEADING_ATTRIBUTES) {
+            self.parse_setext_heading_attributes(node_ix)
+        } else {
+            None
+        };
+        self.tree[node_ix].item.body = ItemBody::Heading(level, attrs);
 
         // strip trailing whitespace
         if let Some(cur_ix) = self.tree.cur() {
@@ -402,17 +515,97 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         Some(ix + n)
     }
 
-    /// Parse a line of input, appending text and items to tree.
+    /// Removes an attribute block from the end of the last line of a setext
+    /// heading, returning the parsed attributes.
+    fn parse_setext_heading_attributes(&mut self, node_ix: TreeIndex) -> Option<HeadingIndex> {
+        let mut children = Vec::new();
+        let mut child = self.tree[node_ix].child;
+        while let Some(child_ix) = child {
+            children.push(child_ix);
+            child = self.tree[child_ix].next;
+        }
+        let line_start = children
+            .iter()
+            .rposition(|&ix| {
+                matches!(
+                    self.tree[ix].item.body,
+                    ItemBody::SoftBreak | ItemBody::HardBreak
+                )
+            })
+            .map_or(0, |i| i + 1);
+        let start = self.tree[*children.get(line_start)?].item.start;
+        let end = self.tree[*children.last()?].item.end;
+        let (len, attrs) = parse_heading_attributes(&self.text[start..end])?;
+        let content_end = start + len;
+
+        // drop the nodes of the attribute block, and the line break before it
+        // if it was alone on its line
+        let keep = children[line_start..]
+            .iter()
+            .rposition(|&ix| self.tree[ix].item.start < content_end);
+        let removed = match keep {
+            Some(i) => {
+                let last_ix = children[line_start + i];
+                let item = &mut self.tree[last_ix].item;
+                item.end = item.end.min(content_end);
+                line_start + i + 1
+            }
+            None => line_start.saturating_sub(1),
+        };
+        // The heading may have been appended after its children when it was
+        // split off a paragraph, so the nodes are unlinked rather than removed.
+        if removed < children.len() {
+            let prev = removed.checked_sub(1).map(|i| children[i]);
+            self.tree.truncate_siblings(prev);
+        }
+        Some(self.allocs.allocate_heading(attrs))
+    }
+
+    /// Parse a line of input, appending text and items to tree. If `end` is
+    /// given, the text stops there rather than at the end of the line.
     ///
     /// Returns: index after line and an item representing the break.
-    fn parse_line(&mut self, start: usize, mode: TableParseMode) -> (usize, Option<Item>) {
-        let bytes = &self.text.as_bytes();
+    fn parse_line(
+        &mut self,
+        start: usize,
+        end: Option<usize>,
+        mode: TableParseMode,
+    ) -> (usize, Option<Item>) {
+        let bytes = &self.text.as_bytes()[..end.unwrap_or(self.text.len())];
         let mut pipes = 0;
         let mut last_pipe_ix = start;
         let mut begin_text = start;
+        let gfm_autolinks = self.options.contains(Options::ENABLE_GFM_AUTOLINKS);
+        let smart_punctuation = self.options.contains(Options::ENABLE_SMART_PUNCTUATION);
+        let mut autolink_checks = ExtendedAutolinkChecks {
+            url_end: start,
+            email_end: start,
+        };
 
         let (final_ix, brk) =
             iterate_special_bytes(&self.lookup_table, bytes, start, |ix, byte| {
+                if gfm_autolinks {
+                    if let Some((link_start, link_end, link_type, dest)) = self
+                        .scan_extended_autolink(bytes, start, begin_text, ix, &mut autolink_checks)
+                    {
+                        self.tree.append_text(begin_text, link_start);
+                        let link_ix = self.allocs.allocate_link(link_type, dest, "".into(), None);
+                        self.tree.append(Item {
+                            start: link_start,
+                            end: link_end,
+                            body: ItemBody::Link(link_ix),
+                        });
+                        self.tree.push();
+                        self.tree.append(Item {
+                            start: link_start,
+                            end: link_end,
+                            body: ItemBody::Text,
+                        });
+                        self.tree.pop();
+                        begin_text = link_end;
+                        return LoopInstruction::ContinueAndSkip(link_end - ix - 1);
+                    }
+                }
                 match byte {
                     b'\n' | b'\r' => {
                         if let TableParseMode::Active = mode {
@@ -494,7 +687,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
                         )
                     }
                     b'\\' => {
-                        if ix + 1 < self.text.len() && is_ascii_punctuation(bytes[ix + 1]) {
+                        if ix + 1 < bytes.len() && is_ascii_punctuation(bytes[ix + 1]) {
                             self.tree.append_text(begin_text, ix);
                             if bytes[ix + 1] == b'`' {
                                 let count = 1 + scan_ch_repeat(&bytes[(ix + 2)..], b'`');
@@ -513,12 +706,20 @@ impl<'a, 'b> FirstPass<'a, 'b> {
                             LoopInstruction::ContinueAndSkip(0)
                         }
                     }
-                    c @ b'*' | c @ b'_' | c @ b'~' => {
+                    c if c == b'*' || c == b'_' || c == b'~' || Some(c) == self.extra_emphasis => {
                         let string_suffix = &self.text[ix..];
                         let count = 1 + scan_ch_repeat(&string_suffix.as_bytes()[1..], c);
                         let can_open = delim_run_can_open(self.text, string_suffix, count, ix);
                         let can_close = delim_run_can_close(self.text, string_suffix, count, ix);
-                        let is_valid_seq = c != b'~' || count == 2;
+                        // strikethrough and extra emphasis take exactly two delimiters
+                        let is_valid_seq = c == b'*' || c == b'_' || count == 2;
+                        #[cfg(feature = "delimiter-runs")]
+                        self.allocs.delimiter_runs.push(crate::parse::DelimiterRun {
+                            range: ix..(ix + count),
+                            delimiter: c as char,
+                            can_open,
+                            can_close,
+                        });
 
                         if (can_open || can_close) && is_valid_seq {
                             self.tree.append_text(begin_text, ix);
@@ -557,7 +758,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
                         LoopInstruction::ContinueAndSkip(0)
                     }
                     b'!' => {
-                        if ix + 1 < self.text.len() && bytes[ix + 1] == b'[' {
+                        if ix + 1 < bytes.len() && bytes[ix + 1] == b'[' {
                             self.tree.append_text(begin_text, ix);
                             self.tree.append(Item {
                                 start: ix,
@@ -612,7 +813,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
                             LoopInstruction::ContinueAndSkip(0)
                         }
                     }
-                    b'.' => {
+                    b'.' if smart_punctuation => {
                         if ix + 2 < bytes.len() && bytes[ix + 1] == b'.' && bytes[ix + 2] == b'.' {
                             self.tree.append_text(begin_text, ix);
                             self.tree.append(Item {
@@ -689,6 +890,61 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         (final_ix, brk)
     }
 
+    /// Checks for an extended autolink containing the special byte at `ix`.
+    /// The link starts at or after `text_start`, the start of the text that
+    /// hasn't been appended to the tree yet.
+    ///
+    /// Returns the range of the link, its type and its destination.
+    fn scan_extended_autolink(
+        &self,
+        bytes: &[u8],
+        line_start: usize,
+        text_start: usize,
+        ix: usize,
+        checked: &mut ExtendedAutolinkChecks,
+    ) -> Option<(usize, usize, LinkType, CowStr<'a>)> {
+        // urls can only follow whitespace and some delimiters
+        let url_start = match bytes[ix] {
+            b':' if bytes[..ix].ends_with(b"https") => Some(ix - 5),
+            b':' if bytes[..ix].ends_with(b"http") => Some(ix - 4),
+            b'.' if bytes[..ix].ends_with(b"www") => Some(ix - 3),
+            _ => None,
+        }
+        .filter(|&start| {
+            start >= text_start
+                && start >= checked.url_end
+                && (start == line_start
+                    || is_ascii_whitespace(bytes[start - 1])
+                    || matches!(bytes[start - 1], b'*' | b'_' | b'~' | b'('))
+        });
+        if let Some(start) = url_start {
+            let stop_at_pipe = self.options.contains(Options::ENABLE_TABLES);
+            if let Some(len) = scan_extended_url(&bytes[start..], stop_at_pipe) {
+                let url = &self.text[start..start + len];
+                let dest = if url.starts_with("www.") {
+                    format!("http://{}", url).into()
+                } else {
+                    url.into()
+                };
+                return Some((start, start + len, LinkType::Autolink, dest));
+            }
+            // a `www.` later in the same domain doesn't start a valid link either
+            checked.url_end = start + scan_while(&bytes[start..], is_extended_domain_char);
+        }
+
+        // the local part of an email address is only scanned once, from the
+        // first special byte in it
+        if ix >= checked.email_end && matches!(bytes[ix], b'@' | b'_' | b'.' | b'-') {
+            let start = ix - scan_rev_while(&bytes[text_start..ix], is_extended_email_local);
+            checked.email_end = ix + scan_while(&bytes[ix..], is_extended_email_local) + 1;
+            if let Some(len) = scan_extended_email(&bytes[start..]) {
+                let email = &self.text[start..start + len];
+                return Some((start, start + len, LinkType::Email, email.into()));
+            }
+        }
+        None
+    }
+
     /// Check whether we should allow a paragraph interrupt by lists. Only non-empty
     /// lists are allowed.
     fn interrupt_paragraph_by_list(&self, suffix: &[u8]) -> bool {
@@ -818,6 +1074,16 @@ impl<'a, 'b> FirstPass<'a, 'b> {
             self.tree[child].item.end = last_nonblank_ix;
         }
         self.pop(end_ix);
+
+        // The trimmed blank lines still separate this block from the next one,
+        // which makes the list loose.
+        if last_line_blank {
+            if let Some(node_ix) = self.tree.peek_up() {
+                if !matches!(self.tree[node_ix].item.body, ItemBody::BlockQuote) {
+                    self.last_line_blank = true;
+                }
+            }
+        }
         ix
     }
 
@@ -993,7 +1259,7 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         let heading_ix = self.tree.append(Item {
             start: ix,
             end: 0, // set later
-            body: ItemBody::Heading(atx_level),
+            body: ItemBody::Heading(atx_level, None),
         });
         ix += atx_level as usize;
         // next char is space or eol (guaranteed by scan_atx_heading)
@@ -1009,12 +1275,28 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         // now handle the header text
         let header_start = ix;
         let header_node_idx = self.tree.push(); // so that we can set the endpoint later
-        ix = self.parse_line(ix, TableParseMode::Disabled).0;
+        let mut content_end = None;
+        if self.options.contains(Options::ENABLE_HEADING_ATTRIBUTES) {
+            let header_end = header_start + scan_nextline(&bytes[header_start..]);
+            let header_text = &self.text[header_start..header_end];
+            if let Some((len, attrs)) = parse_heading_attributes(header_text) {
+                let attrs_ix = self.allocs.allocate_heading(attrs);
+                self.tree[header_node_idx].item.body = ItemBody::Heading(atx_level, Some(attrs_ix));
+                content_end = Some(header_start + len);
+            }
+        }
+        if let Some(end) = content_end {
+            // the attribute block is left out of the header text
+            self.parse_line(ix, Some(end), TableParseMode::Disabled);
+            ix += scan_nextline(&bytes[ix..]);
+        } else {
+            ix = self.parse_line(ix, None, TableParseMode::Disabled).0;
+        }
         self.tree[header_node_idx].item.end = ix;
 
         // remove trailing matter from header text
         if let Some(cur_ix) = self.tree.cur() {
-            let header_text = &bytes[header_start..ix];
+            let header_text = &bytes[header_start..content_end.unwrap_or(ix)];
             let mut limit = header_text
                 .iter()
                 .rposition(|&b| !(b == b'\n' || b == b'\r' || b == b' '))
@@ -1070,7 +1352,9 @@ impl<'a, 'b> FirstPass<'a, 'b> {
             let bytes_scanned = line_start.bytes_scanned();
 
             let suffix = &bytes[bytes_scanned..];
-            if self.interrupt_paragraph_by_list(suffix) || scan_paragraph_interrupt(suffix) {
+            if self.interrupt_paragraph_by_list(suffix)
+                || scan_paragraph_interrupt(suffix, self.options, self.hrule_min)
+            {
                 None
             } else {
                 Some(bytes_scanned)
@@ -1091,7 +1375,14 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         }
         i += 1;
         let (bytecount, link_def) = self.scan_refdef(start, start + i)?;
-        Some((bytecount + i, UniCase::new(label), link_def))
+        let case_sensitive = self
+            .options
+            .contains(Options::ENABLE_CASE_SENSITIVE_REFERENCES);
+        Some((
+            bytecount + i,
+            LinkLabel::new(label, case_sensitive),
+            link_def,
+        ))
     }
 
     /// Returns number of bytes and number of newlines
@@ -1118,6 +1409,30 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         Some((i, newlines))
     }
 
+    /// Removes the container prefixes, such as `> `, from the continuation
+    /// lines of a multi-line refdef title and unescapes it. Returns `None` if a
+    /// continuation line is blank once its prefix is removed.
+    fn strip_title_containers(&self, title: &'a str) -> Option<CowStr<'a>> {
+        if self.tree.spine_len() == 0 || !title.contains('\n') {
+            return Some(unescape(title));
+        }
+        let mut lines = title.split('\n');
+        let mut stripped = String::with_capacity(title.len());
+        stripped.push_str(lines.next().unwrap());
+        while let Some(line) = lines.next() {
+            let mut line_start = LineStart::new(line.as_bytes());
+            scan_containers(&self.tree, &mut line_start);
+            let line = &line[line_start.bytes_scanned()..];
+            // the last line ends with the closing delimiter
+            if lines.clone().next().is_some() && scan_blank_line(line.as_bytes()).is_some() {
+                return None;
+            }
+            stripped.push('\n');
+            stripped.push_str(line);
+        }
+        Some(unescape(&stripped).into_string().into())
+    }
+
     /// Returns # of bytes and definition.
     /// Assumes the label of the reference including colon has already been scanned.
     fn scan_refdef(&self, span_start: usize, start: usize) -> Option<(usize, LinkDef<'a>)> {
@@ -1163,10 +1478,12 @@ impl<'a, 'b> FirstPass<'a, 'b> {
 
         // scan title
         // if this fails but newline == 1, return also a refdef without title
-        if let Some((title_length, title)) = scan_refdef_title(&self.text[i..]) {
+        let title = scan_refdef_title(&self.text[i..]).and_then(|(title_length, title)| {
+            Some((title_length, self.strip_title_containers(title)?))
+        });
+        let title = if let Some((title_length, title)) = title {
             i += title_length;
-            backup.1.span = span_start..i;
-            backup.1.title = Some(unescape(title));
+            title
         } else if newlines > 0 {
             return Some(backup);
         } else {
@@ -1174,8 +1491,11 @@ impl<'a, 'b> FirstPass<'a, 'b> {
         };
 
         // scan EOL
+        // the title only counts if nothing follows it on its line
         if let Some(bytes) = scan_blank_line(&bytes[i..]) {
             backup.0 = i + bytes - start;
+            backup.1.span = span_start..i;
+            backup.1.title = Some(title);
             Some(backup)
         } else if newlines > 0 {
             Some(backup)
@@ -1185,6 +1505,29 @@ impl<'a, 'b> FirstPass<'a, 'b> {
     }
 }
 
+/// Position of a line in a paragraph, other than the first one.
+#[derive(Copy, Clone)]
+struct LineSplit {
+    /// Last node of the text of the previous lines.
+    text_end: TreeIndex,
+    /// Node after which the nodes of the line follow, either `text_end` or
+    /// the line break after it.
+    line_prev: TreeIndex,
+    /// Start of the line, before any container markers.
+    line_begin: usize,
+    /// Start of the content of the line.
+    content_start: usize,
+}
+
+/// Positions in a line before which special bytes are known not to be part
+/// of an extended autolink, so the same text isn't scanned over and over.
+struct ExtendedAutolinkChecks {
+    /// End of the domain of the last URL that wasn't valid.
+    url_end: usize,
+    /// End of the local part of the last email address that was checked.
+    email_end: usize,
+}
+
 /// Scanning modes for `Parser`'s `parse_line` method.
 #[derive(PartialEq, Eq, Copy, Clone)]
 enum TableParseMode {
@@ -1221,11 +1564,11 @@ fn count_header_cols(
 /// Checks whether we should break a paragraph on the given input.
 /// Note: lists are dealt with in `interrupt_paragraph_by_list`, because determing
 /// whether to break on a list requires additional context.
-fn scan_paragraph_interrupt(bytes: &[u8]) -> bool {
+fn scan_paragraph_interrupt(bytes: &[u8], options: Options, hrule_min: usize) -> bool {
     if scan_eol(bytes).is_some()
-        || scan_hrule(bytes).is_ok()
-        || scan_atx_heading(bytes).is_some()
-        || scan_code_fence(bytes).is_some()
+        || scan_hrule(bytes, hrule_min).is_ok()
+        || scan_allowed_atx_heading(bytes, options).is_some()
+        || scan_allowed_code_fence(bytes, options).is_some()
         || scan_blockquote_start(bytes).is_some()
     {
         return true;
@@ -1235,6 +1578,61 @@ fn scan_paragraph_interrupt(bytes: &[u8]) -> bool {
             || is_html_tag(scan_html_block_tag(&bytes[1..]).1))
 }
 
+/// Scans an ATX heading opening sequence, ignoring headings without content
+/// if they are disabled in the options.
+fn scan_allowed_atx_heading(bytes: &[u8], options: Options) -> Option<HeadingLevel> {
+    scan_atx_heading(bytes).filter(|_| {
+        !options.contains(Options::DISABLE_EMPTY_ATX_HEADINGS) || !is_empty_atx_heading(bytes)
+    })
+}
+
+/// Scans a code fence opening, ignoring fence characters disabled in the options.
+fn scan_allowed_code_fence(bytes: &[u8], options: Options) -> Option<(usize, u8)> {
+    scan_code_fence(bytes).filter(|&(_, fence_ch)| {
+        if fence_ch == b'~' {
+            !options.contains(Options::DISABLE_TILDE_FENCES)
+        } else {
+            !options.contains(Options::DISABLE_BACKTICK_FENCES)
+        }
+    })
+}
+
+/// Parses an attribute block such as `{#id .class1 .class2}` at the end of
+/// heading text, ignoring trailing whitespace. Every attribute has to be an
+/// id or a class, and the opening brace must not be escaped, otherwise the
+/// braces are left as literal text.
+///
+/// Returns the length of the text before the block, without the whitespace
+/// separating them, and the attributes.
+fn parse_heading_attributes(text: &str) -> Option<(usize, HeadingAttributes<'_>)> {
+    let text = text.trim_end_matches(&[' ', '\t', '\n', '\r'][..]);
+    if !text.ends_with('}') {
+        return None;
+    }
+    let block_start = text[..text.len() - 1].rfind('{')?;
+    let block = &text[block_start + 1..text.len() - 1];
+    let backslashes = scan_rev_while(&text.as_bytes()[..block_start], |b| b == b'\\');
+    if block.contains('}') || backslashes % 2 == 1 {
+        return None;
+    }
+
+    let mut attrs = HeadingAttributes::default();
+    for attr in block.split_ascii_whitespace() {
+        if attr.len() > 1 && attr.starts_with('#') {
+            attrs.id = Some(&attr[1..]);
+        } else if attr.len() > 1 && attr.starts_with('.') {
+            attrs.classes.push(&attr[1..]);
+        } else {
+            return None;
+        }
+    }
+    if attrs == HeadingAttributes::default() {
+        return None;
+    }
+    let content_end = text[..block_start].trim_end_matches(&[' ', '\t'][..]).len();
+    Some((content_end, attrs))
+}
+
 /// Assumes `text_bytes` is preceded by `<`.
 fn get_html_end_tag(text_bytes: &[u8]) -> Option<&'static str> {
     static BEGIN_TAGS: &[&[u8]; 3] = &[b"pre", b"style", b"script"];
@@ -1380,21 +1778,21 @@ fn delim_run_can_close(s: &str, suffix: &str, run_len: usize, ix: usize) -> bool
     next_char.is_whitespace() || is_punctuation(next_char)
 }
 
-fn create_lut(options: &Options) -> LookupTable {
+fn create_lut(options: &Options, extra_emphasis: Option<u8>) -> LookupTable {
     #[cfg(all(target_arch = "x86_64", feature = "simd"))]
     {
         LookupTable {
-            simd: simd::compute_lookup(options),
-            scalar: special_bytes(options),
+            simd: simd::compute_lookup(options, extra_emphasis),
+            scalar: special_bytes(options, extra_emphasis),
         }
     }
     #[cfg(not(all(target_arch = "x86_64", feature = "simd")))]
     {
-        special_bytes(options)
+        special_bytes(options, extra_emphasis)
     }
 }
 
-fn special_bytes(options: &Options) -> [bool; 256] {
+fn special_bytes(options: &Options, extra_emphasis: Option<u8>) -> [bool; 256] {
     let mut bytes = [false; 256];
     let standard_bytes = [
         b'\n', b'\r', b'*', b'_', b'&', b'\\', b'[', b']', b'<', b'!', b'`',
@@ -1414,6 +1812,14 @@ fn special_bytes(options: &Options) -> [bool; 256] {
             bytes[byte as usize] = true;
         }
     }
+    if options.contains(Options::ENABLE_GFM_AUTOLINKS) {
+        for &byte in b":.@" {
+            bytes[byte as usize] = true;
+        }
+    }
+    if let Some(byte) = extra_emphasis {
+        bytes[byte as usize] = true;
+    }
 
     bytes
 }
@@ -1520,7 +1926,7 @@ mod simd {
     /// special marker bytes. This is effectively a 128 element 2d bitvector,
     /// that can be indexed by a four bit row index (the lower nibble)
     /// and a three bit column index (upper nibble).
-    pub(super) fn compute_lookup(options: &Options) -> [u8; 16] {
+    pub(super) fn compute_lookup(options: &Options, extra_emphasis: Option<u8>) -> [u8; 16] {
         let mut lookup = [0u8; 16];
         let standard_bytes = [
             b'\n', b'\r', b'*', b'_', b'&', b'\\', b'[', b']', b'<', b'!', b'`',
@@ -1540,6 +1946,14 @@ mod simd {
                 add_lookup_byte(&mut lookup, byte);
             }
         }
+        if options.contains(Options::ENABLE_GFM_AUTOLINKS) {
+            for &byte in b":.@" {
+                add_lookup_byte(&mut lookup, byte);
+            }
+        }
+        if let Some(byte) = extra_emphasis {
+            add_lookup_byte(&mut lookup, byte);
+        }
 
         lookup
     }
@@ -1686,7 +2100,7 @@ mod simd {
             opts.insert(Options::ENABLE_STRIKETHROUGH);
             opts.insert(Options::ENABLE_TASKLISTS);
 
-            let lut = create_lut(&opts);
+            let lut = create_lut(&opts, None);
             let mut indices = vec![];
 
             iterate_special_bytes::<_, i32>(&lut, bytes, 0, |ix, _byte_ty| {
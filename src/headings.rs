@@ -0,0 +1,599 @@
+//! Document structure checks and utilities built on heading events.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::{Range, RangeInclusive};
+
+use crate::{Event, HeadingLevel, LinkType, Tag};
+
+/// A heading in a document outline, along with the headings nested under it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutlineNode {
+    /// Level of the heading.
+    pub level: HeadingLevel,
+    /// Plain text of the heading, without any markup.
+    pub title: String,
+    /// Id given in the attribute block of the heading, if any.
+    pub id: Option<String>,
+    /// Source range of the heading.
+    pub range: Range<usize>,
+    /// Headings following this one, up to the next heading of the same or a
+    /// higher level.
+    pub children: Vec<OutlineNode>,
+}
+
+/// Returns the source ranges of headings that skip one or more levels
+/// relative to the heading preceding them, such as an `h3` directly after
+/// an `h1`. Headings are considered in document order, regardless of the
+/// containers they appear in. Returning to a higher level is always allowed,
+/// and the first heading of the document is never reported.
+///
+/// The input is an iterator of events and their source ranges, as produced
+/// by [`Parser::into_offset_iter`](../struct.Parser.html#method.into_offset_iter).
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{headings, Parser};
+///
+/// let markdown = "# Title\n\n### Details\n";
+/// let skipped = headings::find_skipped_levels(Parser::new(markdown).into_offset_iter());
+///
+/// assert_eq!(skipped, vec![9..21]);
+/// assert_eq!(&markdown[skipped[0].clone()], "### Details\n");
+/// ```
+pub fn find_skipped_levels<'a, I>(iter: I) -> Vec<Range<usize>>
+where
+    I: IntoIterator<Item = (Event<'a>, Range<usize>)>,
+{
+    let mut previous: Option<HeadingLevel> = None;
+    let mut skipped = Vec::new();
+
+    for (event, range) in iter {
+        if let Event::Start(Tag::Heading(level, ..)) = event {
+            if let Some(previous) = previous {
+                if level as usize > previous as usize + 1 {
+                    skipped.push(range);
+                }
+            }
+            previous = Some(level);
+        }
+    }
+
+    skipped
+}
+
+/// Builds the outline of a document from its headings, returning the top
+/// level nodes. Each heading is nested under the closest preceding heading of
+/// a higher level, so a heading that skips levels becomes a direct child of
+/// that heading. Headings without such a preceding heading are top level
+/// nodes.
+///
+/// The input is an iterator of events and their source ranges, as produced
+/// by [`Parser::into_offset_iter`](../struct.Parser.html#method.into_offset_iter).
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{headings, Parser};
+///
+/// let markdown = "# Title\n\n## *First* part\n\n## Second part\n";
+/// let outline = headings::outline(Parser::new(markdown).into_offset_iter());
+///
+/// assert_eq!(outline.len(), 1);
+/// assert_eq!(outline[0].title, "Title");
+/// assert_eq!(outline[0].children[0].title, "First part");
+/// ```
+pub fn outline<'a, I>(iter: I) -> Vec<OutlineNode>
+where
+    I: IntoIterator<Item = (Event<'a>, Range<usize>)>,
+{
+    // Headings that may still get children, from the outermost inwards.
+    let mut stack: Vec<OutlineNode> = Vec::new();
+    let mut roots = Vec::new();
+    let mut in_heading = false;
+
+    for (event, range) in iter {
+        match event {
+            Event::Start(Tag::Heading(level, id, _)) => {
+                while matches!(stack.last(), Some(node) if node.level >= level) {
+                    close_outline_node(&mut stack, &mut roots);
+                }
+                stack.push(OutlineNode {
+                    level,
+                    title: String::new(),
+                    id: id.map(|id| id.to_string()),
+                    range,
+                    children: Vec::new(),
+                });
+                in_heading = true;
+            }
+            Event::End(Tag::Heading(..)) => in_heading = false,
+            Event::Text(text) | Event::Code(text) if in_heading => {
+                stack.last_mut().unwrap().title.push_str(&text);
+            }
+            Event::SoftBreak | Event::HardBreak if in_heading => {
+                stack.last_mut().unwrap().title.push(' ');
+            }
+            _ => {}
+        }
+    }
+    while !stack.is_empty() {
+        close_outline_node(&mut stack, &mut roots);
+    }
+
+    roots
+}
+
+fn close_outline_node(stack: &mut Vec<OutlineNode>, roots: &mut Vec<OutlineNode>) {
+    if let Some(node) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+/// Replaces each paragraph consisting of just `[TOC]` or `[[TOC]]` with a
+/// table of contents: a nested list of links to the headings of the document
+/// whose level is in the given range. Headings above the range don't appear,
+/// but their subheadings do.
+///
+/// The linked headings get an anchor as their first child, an `Html` event
+/// with an `id` derived from the heading text, such as `<a id="getting-started"></a>`.
+/// Ids are made unique by appending a number. Headings with an id of their
+/// own, from an attribute block, are linked by that id instead.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{headings, html, HeadingLevel, Parser};
+///
+/// let markdown = "[TOC]\n\n# Title\n\n## Getting started\n";
+/// let events = headings::insert_toc(Parser::new(markdown), HeadingLevel::H2..=HeadingLevel::H6);
+///
+/// let mut html_buf = String::new();
+/// html::push_html(&mut html_buf, events);
+/// assert_eq!(
+///     html_buf,
+///     "<ul>\n<li><a href=\"#getting-started\">Getting started</a></li>\n</ul>\n\
+///      <h1>Title</h1>\n<h2><a id=\"getting-started\"></a>Getting started</h2>\n"
+/// );
+/// ```
+pub fn insert_toc<'a, I>(iter: I, levels: RangeInclusive<HeadingLevel>) -> Vec<Event<'a>>
+where
+    I: IntoIterator<Item = Event<'a>>,
+{
+    let events: Vec<_> = iter.into_iter().collect();
+    let tree = outline(events.iter().cloned().map(|event| (event, 0..0)));
+
+    // Ids of the linked headings, in document order.
+    let mut ids = Vec::new();
+    let mut used = HashMap::new();
+    assign_toc_ids(&tree, &levels, &mut used, &mut ids);
+    let mut toc = Vec::new();
+    let mut ids_iter = ids.iter();
+    write_toc_items(&tree, &levels, &mut ids_iter, &mut toc);
+
+    let mut result = Vec::with_capacity(events.len());
+    let mut ids_iter = ids.iter();
+    let mut i = 0;
+    while i < events.len() {
+        if let Some(len) = toc_marker_len(&events[i..]) {
+            if !toc.is_empty() {
+                result.push(Event::Start(Tag::List(None)));
+                result.extend(toc.iter().cloned());
+                result.push(Event::End(Tag::List(None)));
+            }
+            i += len;
+            continue;
+        }
+        let event = events[i].clone();
+        i += 1;
+        let anchored = match event {
+            Event::Start(Tag::Heading(level, ref id, _)) if levels.contains(&level) => {
+                Some(id.is_none())
+            }
+            _ => None,
+        };
+        result.push(event);
+        if let Some(needs_anchor) = anchored {
+            let id = ids_iter.next().unwrap();
+            if needs_anchor {
+                result.push(Event::Html(format!("<a id=\"{}\"></a>", id).into()));
+            }
+        }
+    }
+    result
+}
+
+/// Returns the number of events making up the table of contents marker at
+/// the start of the events, if there is one.
+fn toc_marker_len(events: &[Event]) -> Option<usize> {
+    if events.first() != Some(&Event::Start(Tag::Paragraph)) {
+        return None;
+    }
+    let mut text = String::new();
+    for (i, event) in events.iter().enumerate().skip(1) {
+        match event {
+            Event::Text(t) => text.push_str(t),
+            Event::End(Tag::Paragraph) if text == "[TOC]" || text == "[[TOC]]" => {
+                return Some(i + 1);
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn assign_toc_ids(
+    nodes: &[OutlineNode],
+    levels: &RangeInclusive<HeadingLevel>,
+    used: &mut HashMap<String, usize>,
+    ids: &mut Vec<String>,
+) {
+    for node in nodes {
+        if levels.contains(&node.level) {
+            let id = match node.id {
+                Some(ref id) => id.clone(),
+                None => {
+                    let slug = slugify(&node.title);
+                    let count = used.entry(slug.clone()).or_insert(0);
+                    let id = if *count == 0 {
+                        slug.clone()
+                    } else {
+                        format!("{}-{}", slug, count)
+                    };
+                    *count += 1;
+                    id
+                }
+            };
+            ids.push(id);
+        }
+        assign_toc_ids(&node.children, levels, used, ids);
+    }
+}
+
+fn write_toc_items<'a, 'i>(
+    nodes: &[OutlineNode],
+    levels: &RangeInclusive<HeadingLevel>,
+    ids: &mut impl Iterator<Item = &'i String>,
+    toc: &mut Vec<Event<'a>>,
+) {
+    for node in nodes {
+        if node.level > *levels.end() {
+            continue;
+        }
+        if node.level < *levels.start() {
+            write_toc_items(&node.children, levels, ids, toc);
+            continue;
+        }
+        let href = format!("#{}", ids.next().unwrap());
+        toc.push(Event::Start(Tag::Item));
+        toc.push(Event::Start(Tag::Link(
+            LinkType::Inline,
+            href.clone().into(),
+            "".into(),
+            None,
+        )));
+        toc.push(Event::Text(node.title.clone().into()));
+        toc.push(Event::End(Tag::Link(
+            LinkType::Inline,
+            href.into(),
+            "".into(),
+            None,
+        )));
+        let mut children = Vec::new();
+        write_toc_items(&node.children, levels, ids, &mut children);
+        if !children.is_empty() {
+            toc.push(Event::Start(Tag::List(None)));
+            toc.append(&mut children);
+            toc.push(Event::End(Tag::List(None)));
+        }
+        toc.push(Event::End(Tag::Item));
+    }
+}
+
+/// Turns heading text into an id: lowercase alphanumeric characters, with
+/// spaces replaced by `-` and other characters dropped.
+fn slugify(title: &str) -> String {
+    let slug: String = title
+        .trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
+
+/// What [`demote_extra_h1s`] does with the `h1` headings after the first one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExtraH1Policy {
+    /// Leave all headings unchanged.
+    Keep,
+    /// Turn the extra `h1` headings into `h2` headings, leaving the other
+    /// headings unchanged.
+    DemoteToH2,
+    /// Demote the extra `h1` headings, along with the headings in their
+    /// sections, by one level. `h6` headings stay at `h6`.
+    DemoteSections,
+}
+
+/// Adapts an event stream so that it contains at most one `h1` heading,
+/// according to the policy. The first `h1` heading is never changed, and the
+/// `End` event of a heading always gets the same level as its `Start` event.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{html, Parser};
+/// use pulldown_cmark::headings::{demote_extra_h1s, ExtraH1Policy};
+///
+/// let parser = Parser::new("# a\n# b\n## c\n");
+/// let mut html_buf = String::new();
+/// html::push_html(&mut html_buf, demote_extra_h1s(parser, ExtraH1Policy::DemoteSections));
+/// assert_eq!(html_buf, "<h1>a</h1>\n<h2>b</h2>\n<h3>c</h3>\n");
+/// ```
+pub fn demote_extra_h1s<'a, I>(iter: I, policy: ExtraH1Policy) -> DemoteExtraH1s<I::IntoIter>
+where
+    I: IntoIterator<Item = Event<'a>>,
+{
+    DemoteExtraH1s {
+        iter: iter.into_iter(),
+        policy,
+        seen_h1: false,
+        in_extra_section: false,
+        current: HeadingLevel::H1,
+    }
+}
+
+/// Iterator returned by [`demote_extra_h1s`].
+#[derive(Clone, Debug)]
+pub struct DemoteExtraH1s<I> {
+    iter: I,
+    policy: ExtraH1Policy,
+    seen_h1: bool,
+    /// Whether an extra `h1` heading has been seen, which puts all following
+    /// headings in the section of one.
+    in_extra_section: bool,
+    /// Level given to the heading that was started last.
+    current: HeadingLevel,
+}
+
+impl<I> DemoteExtraH1s<I> {
+    fn new_level(&mut self, level: HeadingLevel) -> HeadingLevel {
+        let extra = level == HeadingLevel::H1 && self.seen_h1;
+        if level == HeadingLevel::H1 {
+            self.seen_h1 = true;
+        }
+        match self.policy {
+            ExtraH1Policy::Keep => level,
+            ExtraH1Policy::DemoteToH2 if extra => HeadingLevel::H2,
+            ExtraH1Policy::DemoteToH2 => level,
+            ExtraH1Policy::DemoteSections => {
+                self.in_extra_section |= extra;
+                if self.in_extra_section {
+                    HeadingLevel::try_from(level as usize + 1).unwrap_or(HeadingLevel::H6)
+                } else {
+                    level
+                }
+            }
+        }
+    }
+}
+
+impl<'a, I> Iterator for DemoteExtraH1s<I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        Some(match self.iter.next()? {
+            Event::Start(Tag::Heading(level, id, classes)) => {
+                self.current = self.new_level(level);
+                Event::Start(Tag::Heading(self.current, id, classes))
+            }
+            Event::End(Tag::Heading(_, id, classes)) => {
+                Event::End(Tag::Heading(self.current, id, classes))
+            }
+            event => event,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        demote_extra_h1s, find_skipped_levels, insert_toc, outline, ExtraH1Policy, OutlineNode,
+    };
+    use crate::{html, HeadingLevel, Options, Parser};
+
+    fn skipped(text: &str) -> Vec<&str> {
+        find_skipped_levels(Parser::new(text).into_offset_iter())
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn consecutive_levels() {
+        assert!(skipped("# a\n## b\n### c\n## d\n# e\n").is_empty());
+    }
+
+    #[test]
+    fn skipped_level() {
+        assert_eq!(vec!["### c\n"], skipped("# a\n### c\n"));
+    }
+
+    #[test]
+    fn going_up_is_allowed() {
+        assert!(skipped("# a\n## b\n### c\n# d\n").is_empty());
+    }
+
+    #[test]
+    fn first_heading_not_reported() {
+        assert!(skipped("### a\n#### b\n").is_empty());
+    }
+
+    #[test]
+    fn setext_and_atx() {
+        assert_eq!(vec!["#### c\n"], skipped("a\n===\n\nb\n---\n\n#### c\n"));
+    }
+
+    #[test]
+    fn nested_in_containers() {
+        let text = "# a\n\n> ## b\n>\n> - #### c\n\n1. ### d\n";
+        assert_eq!(vec!["#### c\n"], skipped(text));
+    }
+
+    #[test]
+    fn multiple_skips() {
+        assert_eq!(
+            vec!["### b\n", "###### d\n"],
+            skipped("# a\n### b\n# c\n###### d\n")
+        );
+    }
+
+    /// Formats an outline as titles, with the children of each heading in
+    /// parentheses.
+    fn outline_titles(text: &str) -> String {
+        fn write(nodes: &[OutlineNode], s: &mut String) {
+            for (i, node) in nodes.iter().enumerate() {
+                if i > 0 {
+                    s.push(' ');
+                }
+                s.push_str(&node.title);
+                if !node.children.is_empty() {
+                    s.push('(');
+                    write(&node.children, s);
+                    s.push(')');
+                }
+            }
+        }
+        let mut s = String::new();
+        write(&outline(Parser::new(text).into_offset_iter()), &mut s);
+        s
+    }
+
+    #[test]
+    fn outline_nesting() {
+        let text = "# a\n\n## b\n\n## c\n\n### d\n\n# e\n";
+        assert_eq!("a(b c(d)) e", outline_titles(text));
+
+        let tree = outline(Parser::new(text).into_offset_iter());
+        assert_eq!(HeadingLevel::H1, tree[0].level);
+        assert_eq!(5..10, tree[0].children[0].range);
+        assert_eq!(HeadingLevel::H3, tree[0].children[1].children[0].level);
+    }
+
+    #[test]
+    fn outline_skipped_levels() {
+        assert_eq!(
+            "a(b c(d)) e",
+            outline_titles("# a\n### b\n## c\n#### d\n# e\n")
+        );
+        assert_eq!("a b(c)", outline_titles("### a\n## b\n### c\n"));
+    }
+
+    #[test]
+    fn outline_title_is_plain_text() {
+        let text = "# *a* `b` [c](d)\n\ne\\\nf\n---\n";
+        assert_eq!("a b c(e f)", outline_titles(text));
+    }
+
+    fn demoted(text: &str, policy: ExtraH1Policy) -> String {
+        let mut s = String::new();
+        html::push_html(&mut s, demote_extra_h1s(Parser::new(text), policy));
+        s
+    }
+
+    #[test]
+    fn demote_extra_h1s_to_h2() {
+        let text = "# a\n## b\n# c\n## d\n# e\n###### f\n";
+        assert_eq!(
+            "<h1>a</h1>\n<h2>b</h2>\n<h2>c</h2>\n<h2>d</h2>\n<h2>e</h2>\n<h6>f</h6>\n",
+            demoted(text, ExtraH1Policy::DemoteToH2)
+        );
+        assert_eq!(
+            "<h1>a</h1>\n<h2>b</h2>\n<h1>c</h1>\n<h2>d</h2>\n<h1>e</h1>\n<h6>f</h6>\n",
+            demoted(text, ExtraH1Policy::Keep)
+        );
+    }
+
+    #[test]
+    fn demote_extra_h1_sections() {
+        let text = "## a\n# b\n## c\n# d\n## e\n# f\n###### g\n";
+        assert_eq!(
+            "<h2>a</h2>\n<h1>b</h1>\n<h2>c</h2>\n<h2>d</h2>\n<h3>e</h3>\n<h2>f</h2>\n<h6>g</h6>\n",
+            demoted(text, ExtraH1Policy::DemoteSections)
+        );
+    }
+
+    #[test]
+    fn toc_at_marker() {
+        let text = "# Doc\n\n[TOC]\n\n## A *b*\n\n### C\n\n#### Deep\n\n## A b\n\n## ?\n";
+        let events = insert_toc(Parser::new(text), HeadingLevel::H2..=HeadingLevel::H3);
+        let mut s = String::new();
+        html::push_html(&mut s, events);
+        assert_eq!(
+            "<h1>Doc</h1>\n\
+             <ul>\n\
+             <li><a href=\"#a-b\">A b</a>\n<ul>\n<li><a href=\"#c\">C</a></li>\n</ul>\n</li>\n\
+             <li><a href=\"#a-b-1\">A b</a></li>\n\
+             <li><a href=\"#section\">?</a></li>\n\
+             </ul>\n\
+             <h2><a id=\"a-b\"></a>A <em>b</em></h2>\n\
+             <h3><a id=\"c\"></a>C</h3>\n\
+             <h4>Deep</h4>\n\
+             <h2><a id=\"a-b-1\"></a>A b</h2>\n\
+             <h2><a id=\"section\"></a>?</h2>\n",
+            s
+        );
+    }
+
+    #[test]
+    fn toc_markers() {
+        let render = |text: &str| {
+            let mut s = String::new();
+            let events = insert_toc(Parser::new(text), HeadingLevel::H1..=HeadingLevel::H6);
+            html::push_html(&mut s, events);
+            s
+        };
+        assert_eq!(
+            "<ul>\n<li><a href=\"#x\">x</a></li>\n</ul>\n<h1><a id=\"x\"></a>x</h1>\n",
+            render("[[TOC]]\n# x\n")
+        );
+        assert_eq!(
+            "<p>[TOC] a</p>\n<p><em>[TOC]</em></p>\n",
+            render("[TOC] a\n\n*[TOC]*\n")
+        );
+        // without headings, the marker is removed
+        assert_eq!("<p>a</p>\n", render("[TOC]\n\na\n"));
+    }
+
+    #[test]
+    fn toc_with_heading_ids() {
+        let text = "[TOC]\n\n# a {#first}\n\n# b\n";
+        let events = insert_toc(
+            Parser::new_ext(text, Options::ENABLE_HEADING_ATTRIBUTES),
+            HeadingLevel::H1..=HeadingLevel::H6,
+        );
+        let mut s = String::new();
+        html::push_html(&mut s, events);
+        assert_eq!(
+            "<ul>\n<li><a href=\"#first\">a</a></li>\n<li><a href=\"#b\">b</a></li>\n</ul>\n\
+             <h1 id=\"first\">a</h1>\n<h1><a id=\"b\"></a>b</h1>\n",
+            s
+        );
+    }
+}
@@ -20,20 +20,206 @@
 
 //! HTML renderer that takes an iterator of events as input.
 
-use std::collections::HashMap;
+use std::cmp::max;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::io::{self, Write};
+use std::ops::Range;
 
 use crate::escape::{escape_href, escape_html, StrWrite, WriteWrapper};
 use crate::strings::CowStr;
 use crate::Event::*;
-use crate::{Alignment, CodeBlockKind, Event, LinkType, Tag};
+use crate::{Alignment, CodeBlockKind, Event, HeadingLevel, LinkType, Tag};
 
 enum TableState {
     Head,
     Body,
 }
 
-struct HtmlWriter<'a, I, W> {
+/// Configuration for the HTML renderer, used by [`push_html_ext`] and
+/// [`write_html_ext`]. The default configuration produces the same output
+/// as [`push_html`] and [`write_html`].
+#[derive(Default)]
+pub struct HtmlConfig<'c> {
+    /// Emit `role` attributes on tables and their rows, and `scope="col"`
+    /// on header cells, for the benefit of assistive technology.
+    pub table_roles: bool,
+    /// Collapse runs of consecutive soft and hard line breaks into a single
+    /// break. The run is rendered as `<br />` if it contains a hard break.
+    pub collapse_breaks: bool,
+    /// Render a paragraph consisting of nothing but an image with a title as
+    /// a `<figure>`, with the title repeated in a `<figcaption>`.
+    pub figures: bool,
+    /// Align table cells with an inline `style="text-align: ..."` attribute
+    /// rather than the `align` attribute.
+    pub inline_alignment_styles: bool,
+    /// Write the address of email autolinks and `mailto:` links as numeric
+    /// character references, in both the `href` attribute and the link text,
+    /// to make it harder for spam bots to harvest them.
+    pub obfuscate_emails: bool,
+    /// Collapse runs of spaces and tabs in heading text into a single space.
+    pub collapse_heading_whitespace: bool,
+    /// Replace each tab in text with this number of spaces. Tabs in code
+    /// blocks and inline code are left alone.
+    pub expand_tabs: Option<usize>,
+    /// Called with the value of every `href`, `src`, `title` and `class`
+    /// attribute taken from the document, before it is escaped and written.
+    /// The returned value is written instead, or the attribute is left out
+    /// if `None` is returned.
+    pub attribute_callback: AttributeCallback<'c>,
+    /// Insert a `<wbr>` break opportunity after each of these characters in
+    /// inline code, so that long code spans such as paths can wrap. Code
+    /// blocks are not affected.
+    pub code_break_after: &'c [char],
+    /// Link each footnote definition back to the references to it with `↩`
+    /// links at the end of the definition. The first reference gets an `id`
+    /// of the footnote name prefixed with `fnref-` as the link target, and
+    /// further references get the same `id` suffixed with `-2`, `-3` and so
+    /// on. When there are several references, the links are numbered, as in
+    /// `↩1 ↩2`. References after the definition are not linked back to.
+    pub footnote_backrefs: bool,
+    /// Give each task list item a sequential `id` of the form `task-1`, and
+    /// reference it from the checkbox with a `data-task-id` attribute.
+    pub task_list_ids: bool,
+    /// Called with the language of every fenced code block that has one, such
+    /// as `js`. The returned language is used for the `language-` class
+    /// instead, so aliases can be mapped to a canonical name. No class is
+    /// written if an empty language is returned.
+    pub language_callback: LanguageCallback<'c>,
+    /// Called with the tag of every element opened for a `Start` event,
+    /// returning extra attributes to write on the element as name and value
+    /// pairs. Both are escaped. Attributes for table heads are written on the
+    /// header row, and those for code blocks on the `pre` element.
+    pub tag_attributes: TagAttributesCallback<'c>,
+    /// Leave out the `href` of links with a `data:` URL, and the `src` of
+    /// images with a `data:` URL unless it has an image media type, such as
+    /// `data:image/png;base64,...`.
+    pub filter_data_urls: bool,
+    /// Write a `<colgroup>` at the start of each table, with a `<col>` for
+    /// each column. Columns with an alignment get a class of the form
+    /// `align-center`, as a hook for styling whole columns.
+    pub table_colgroups: bool,
+    /// Called with every piece of raw HTML in the document, whether a block
+    /// or inline, before it is written. The returned HTML is written instead,
+    /// so it can be sanitized, or escaped to show it as text.
+    pub html_callback: HtmlCallback<'c>,
+    /// Wrap the whole output in an element, given by its name and class,
+    /// such as `("article", "markdown-body")`. No `class` attribute is
+    /// written if the class is empty.
+    pub wrapper: Option<(&'c str, &'c str)>,
+    /// Leave out C0 control characters other than tabs and line endings,
+    /// such as escape characters, from text. The parser already replaces NUL
+    /// characters with U+FFFD.
+    pub strip_control_chars: bool,
+    /// Prepend section numbers such as `1.2.1` to the text of headings. A
+    /// heading is numbered by its place in the document outline, as built
+    /// by [`headings::outline`](../headings/fn.outline.html), so a heading
+    /// that skips levels is numbered as a direct child of the closest
+    /// preceding heading of a higher level.
+    pub heading_numbers: bool,
+}
+
+impl<'c> fmt::Debug for HtmlConfig<'c> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HtmlConfig")
+            .field("table_roles", &self.table_roles)
+            .field("collapse_breaks", &self.collapse_breaks)
+            .field("figures", &self.figures)
+            .field("inline_alignment_styles", &self.inline_alignment_styles)
+            .field("obfuscate_emails", &self.obfuscate_emails)
+            .field(
+                "collapse_heading_whitespace",
+                &self.collapse_heading_whitespace,
+            )
+            .field("expand_tabs", &self.expand_tabs)
+            .field("attribute_callback", &self.attribute_callback.is_some())
+            .field("code_break_after", &self.code_break_after)
+            .field("footnote_backrefs", &self.footnote_backrefs)
+            .field("task_list_ids", &self.task_list_ids)
+            .field("language_callback", &self.language_callback.is_some())
+            .field("tag_attributes", &self.tag_attributes.is_some())
+            .field("filter_data_urls", &self.filter_data_urls)
+            .field("table_colgroups", &self.table_colgroups)
+            .field("html_callback", &self.html_callback.is_some())
+            .field("wrapper", &self.wrapper)
+            .field("strip_control_chars", &self.strip_control_chars)
+            .field("heading_numbers", &self.heading_numbers)
+            .finish()
+    }
+}
+
+/// An attribute passed to [`HtmlConfig::attribute_callback`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AttrName {
+    /// The destination of a link.
+    Href,
+    /// The source of an image.
+    Src,
+    /// The title of a link or image.
+    Title,
+    /// The class of a code block, derived from its language, or the classes
+    /// of a heading.
+    Class,
+    /// The id of a heading.
+    Id,
+}
+
+impl AttrName {
+    fn as_str(self) -> &'static str {
+        match self {
+            AttrName::Href => "href",
+            AttrName::Src => "src",
+            AttrName::Title => "title",
+            AttrName::Class => "class",
+            AttrName::Id => "id",
+        }
+    }
+}
+
+/// Callback to rewrite or drop attribute values, see [`HtmlConfig::attribute_callback`].
+pub type AttributeCallback<'c> = Option<&'c mut dyn FnMut(AttrName, &str) -> Option<CowStr<'_>>>;
+
+/// Callback to rewrite code block languages, see [`HtmlConfig::language_callback`].
+pub type LanguageCallback<'c> = Option<&'c mut dyn FnMut(&str) -> CowStr<'_>>;
+
+/// Where a piece of raw HTML passed to [`HtmlConfig::html_callback`] appears.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HtmlKind {
+    /// An HTML block, or one line of it.
+    Block,
+    /// Inline HTML within a paragraph, heading or table cell, or any HTML
+    /// reported as [`Event::InlineHtml`](../enum.Event.html#variant.InlineHtml).
+    /// Without [`Options::ENABLE_INLINE_HTML_KINDS`](../struct.Options.html#associatedconstant.ENABLE_INLINE_HTML_KINDS),
+    /// inline HTML directly in a tight list item is reported as a block.
+    Inline,
+}
+
+/// Callback to rewrite raw HTML, see [`HtmlConfig::html_callback`].
+pub type HtmlCallback<'c> = Option<&'c mut dyn FnMut(HtmlKind, &str) -> CowStr<'_>>;
+
+/// Callback to add attributes to elements, see [`HtmlConfig::tag_attributes`].
+pub type TagAttributesCallback<'c> =
+    Option<&'c mut dyn FnMut(&Tag) -> Vec<(CowStr<'static>, CowStr<'static>)>>;
+
+/// Whether the character is left out of text when stripping control characters.
+fn is_stripped_control(c: char) -> bool {
+    c.is_ascii_control() && c != '\t' && c != '\n' && c != '\r' && c != '\x7f'
+}
+
+/// Whether the value of the attribute is a `data:` URL that is left out when
+/// filtering them. Only images may use them, with an image media type.
+fn is_unsafe_data_url(name: AttrName, url: &str) -> bool {
+    let is_data = matches!(url.get(..5), Some(scheme) if scheme.eq_ignore_ascii_case("data:"));
+    match name {
+        AttrName::Href => is_data,
+        AttrName::Src => {
+            is_data && !matches!(url.get(5..11), Some(ty) if ty.eq_ignore_ascii_case("image/"))
+        }
+        AttrName::Title | AttrName::Class | AttrName::Id => false,
+    }
+}
+
+struct HtmlWriter<'a, 'c, I, W> {
     /// Iterator supplying events.
     iter: I,
 
@@ -47,14 +233,42 @@ struct HtmlWriter<'a, I, W> {
     table_alignments: Vec<Alignment>,
     table_cell_index: usize,
     numbers: HashMap<CowStr<'a>, usize>,
+    /// Number of references to each footnote seen so far.
+    footnote_refs: HashMap<CowStr<'a>, usize>,
+    /// Number of task list items seen so far.
+    task_count: usize,
+
+    config: HtmlConfig<'c>,
+    /// Line break held back while collapsing breaks. `true` for a hard break.
+    pending_break: Option<bool>,
+    /// Events read ahead from the iterator, to be processed before the rest.
+    lookahead: VecDeque<(Event<'a>, Option<Range<usize>>)>,
+    /// Source range of the event being processed, if known.
+    range: Option<Range<usize>>,
+    /// Start offsets of the lines of the source text, to turn source ranges
+    /// into line and column numbers.
+    line_starts: Vec<usize>,
+    /// Whether we're inside a link whose text is being obfuscated.
+    in_obfuscated_link: bool,
+    /// Set inside a heading while collapsing heading whitespace. `true` when
+    /// the last character written was a space.
+    heading_space: Option<bool>,
+    /// Level and number of the headings enclosing the current position in
+    /// the outline, from the outermost inwards.
+    heading_numbers: Vec<(HeadingLevel, usize)>,
+    /// Whether we're inside a code block.
+    in_code_block: bool,
+    /// Whether we're inside a paragraph, heading or table cell, where raw
+    /// HTML is inline.
+    in_inline_block: bool,
 }
 
-impl<'a, I, W> HtmlWriter<'a, I, W>
+impl<'a, 'c, I, W> HtmlWriter<'a, 'c, I, W>
 where
-    I: Iterator<Item = Event<'a>>,
+    I: Iterator<Item = (Event<'a>, Option<Range<usize>>)>,
     W: StrWrite,
 {
-    fn new(iter: I, writer: W) -> Self {
+    fn new(iter: I, writer: W, config: HtmlConfig<'c>) -> Self {
         Self {
             iter,
             writer,
@@ -63,7 +277,47 @@ where
             table_alignments: vec![],
             table_cell_index: 0,
             numbers: HashMap::new(),
+            footnote_refs: HashMap::new(),
+            task_count: 0,
+            config,
+            pending_break: None,
+            lookahead: VecDeque::new(),
+            in_obfuscated_link: false,
+            heading_space: None,
+            heading_numbers: Vec::new(),
+            in_code_block: false,
+            in_inline_block: false,
+            range: None,
+            line_starts: Vec::new(),
+        }
+    }
+
+    /// Writes the section number of a heading of the given level, followed
+    /// by a space.
+    fn write_heading_number(&mut self, level: HeadingLevel) -> io::Result<()> {
+        // Continue the numbering of the last heading at the same depth of the
+        // outline, if this heading closes it.
+        let mut number = 1;
+        while let Some(&(open, n)) = self.heading_numbers.last() {
+            if open < level {
+                break;
+            }
+            self.heading_numbers.pop();
+            number = n + 1;
+        }
+        self.heading_numbers.push((level, number));
+
+        for (i, &(_, n)) in self.heading_numbers.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_str(".")?;
+            }
+            write!(&mut self.writer, "{}", n)?;
+        }
+        self.write(" ")?;
+        if self.heading_space.is_some() {
+            self.heading_space = Some(true);
         }
+        Ok(())
     }
 
     /// Writes a new line.
@@ -83,26 +337,293 @@ where
         Ok(())
     }
 
+    /// Passes an attribute value through the attribute callback, if any.
+    fn attr_value<'v>(&mut self, name: AttrName, value: &'v str) -> Option<CowStr<'v>> {
+        if self.config.filter_data_urls && is_unsafe_data_url(name, value) {
+            return None;
+        }
+        match self.config.attribute_callback {
+            Some(ref mut callback) => callback(name, value),
+            None => Some(value.into()),
+        }
+    }
+
+    /// Writes an attribute, preceded by a space, unless the attribute
+    /// callback leaves it out.
+    fn write_attr(&mut self, name: AttrName, value: &str) -> io::Result<()> {
+        if let Some(value) = self.attr_value(name, value) {
+            self.write(" ")?;
+            self.write(name.as_str())?;
+            self.write("=\"")?;
+            match name {
+                AttrName::Href | AttrName::Src => escape_href(&mut self.writer, &value)?,
+                AttrName::Title | AttrName::Class | AttrName::Id => {
+                    escape_html(&mut self.writer, &value)?
+                }
+            }
+            self.write("\"")?;
+        }
+        Ok(())
+    }
+
+    /// Writes raw HTML, passing it through the HTML callback, if any.
+    fn write_raw_html(&mut self, kind: HtmlKind, html: &str) -> io::Result<()> {
+        match self.config.html_callback {
+            Some(ref mut callback) => {
+                let html = callback(kind, html);
+                self.write(&html)
+            }
+            None => self.write(html),
+        }
+    }
+
+    /// Writes extra attributes returned by the tag attributes callback.
+    fn write_attrs(&mut self, attrs: &[(CowStr<'static>, CowStr<'static>)]) -> io::Result<()> {
+        for (name, value) in attrs {
+            self.write(" ")?;
+            escape_html(&mut self.writer, name)?;
+            self.write("=\"")?;
+            escape_html(&mut self.writer, value)?;
+            self.write("\"")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the content of a code span, with break opportunities after the
+    /// configured characters, except at the very end.
+    fn write_code_span(&mut self, text: &str) -> io::Result<()> {
+        let break_after = self.config.code_break_after;
+        let mut start = 0;
+        for (ix, c) in text.char_indices() {
+            let end = ix + c.len_utf8();
+            if break_after.contains(&c) && end < text.len() {
+                escape_html(&mut self.writer, &text[start..end])?;
+                self.write("<wbr>")?;
+                start = end;
+            }
+        }
+        escape_html(&mut self.writer, &text[start..])
+    }
+
+    /// Writes every character of `s` as a numeric character reference.
+    fn write_obfuscated(&mut self, s: &str) -> io::Result<()> {
+        for c in s.chars() {
+            write!(&mut self.writer, "&#{};", c as u32)?;
+        }
+        if !s.is_empty() {
+            self.end_newline = false;
+        }
+        Ok(())
+    }
+
+    /// Writes heading text with runs of spaces and tabs collapsed, continuing
+    /// any run from the previous text event of the heading.
+    fn write_heading_text(&mut self, text: &str, mut after_space: bool) -> io::Result<()> {
+        let mut collapsed = String::with_capacity(text.len());
+        for c in text.chars() {
+            let space = c == ' ' || c == '\t';
+            if !(space && after_space) {
+                collapsed.push(if space { ' ' } else { c });
+            }
+            after_space = space;
+        }
+        self.heading_space = Some(after_space);
+        escape_html(&mut self.writer, &collapsed)?;
+        self.end_newline = false;
+        Ok(())
+    }
+
+    /// Writes a `data-sourcepos` attribute for the event being processed, if
+    /// its source range is known. Lines and columns are counted from one, and
+    /// the end position is inclusive.
+    fn write_sourcepos(&mut self) -> io::Result<()> {
+        if let Some(range) = self.range.clone() {
+            let (start_line, start_col) = self.line_col(range.start);
+            let (end_line, end_col) = self.line_col(max(range.start, range.end.saturating_sub(1)));
+            write!(
+                &mut self.writer,
+                " data-sourcepos=\"{}:{}-{}:{}\"",
+                start_line, start_col, end_line, end_col
+            )?;
+        }
+        Ok(())
+    }
+
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        (line + 1, offset - self.line_starts[line] + 1)
+    }
+
+    /// Returns the next event with its source range, taking events that were
+    /// read ahead first.
+    fn next_item(&mut self) -> Option<(Event<'a>, Option<Range<usize>>)> {
+        self.lookahead.pop_front().or_else(|| self.iter.next())
+    }
+
+    /// Returns the next event, and makes its source range the current one.
+    fn next_event(&mut self) -> Option<Event<'a>> {
+        let (event, range) = self.next_item()?;
+        self.range = range;
+        Some(event)
+    }
+
+    /// Reads ahead after the start of a paragraph. If the paragraph consists of
+    /// nothing but an image with a title, the end of the paragraph is consumed
+    /// and the title returned. All other events read remain to be processed.
+    fn lone_titled_image(&mut self) -> Option<CowStr<'a>> {
+        let first = self.next_item()?;
+        let title = match first.0 {
+            Start(Tag::Image(_, _, ref title)) if !title.is_empty() => title.clone(),
+            _ => {
+                self.lookahead.push_front(first);
+                return None;
+            }
+        };
+        let mut items = vec![first];
+        let mut nest = 0;
+        while let Some(item) = self.next_item() {
+            let done = match item.0 {
+                Start(_) => {
+                    nest += 1;
+                    false
+                }
+                End(_) if nest == 0 => true,
+                End(_) => {
+                    nest -= 1;
+                    false
+                }
+                _ => false,
+            };
+            items.push(item);
+            if done {
+                break;
+            }
+        }
+        let next = self.next_item();
+        let lone = matches!(next, Some((End(Tag::Paragraph), _)));
+        if !lone {
+            self.lookahead.extend(next);
+        }
+        for item in items.into_iter().rev() {
+            self.lookahead.push_front(item);
+        }
+        if lone {
+            Some(title)
+        } else {
+            None
+        }
+    }
+
+    /// Writes a figure for an image, the start of which is the next event.
+    fn write_figure(&mut self, caption: CowStr<'a>) -> io::Result<()> {
+        if !self.end_newline {
+            self.write_newline()?;
+        }
+        self.write("<figure")?;
+        self.write_sourcepos()?;
+        self.write(">")?;
+        if let Some(Start(tag)) = self.next_event() {
+            self.start_tag(tag)?;
+        }
+        self.write("<figcaption>")?;
+        escape_html(&mut self.writer, &caption)?;
+        self.write("</figcaption></figure>\n")
+    }
+
+    /// Writes the line break held back while collapsing breaks, if any.
+    fn flush_break(&mut self) -> io::Result<()> {
+        match self.pending_break.take() {
+            Some(true) => self.write("<br />\n"),
+            Some(false) => self.write_newline(),
+            None => Ok(()),
+        }
+    }
+
     fn run(mut self) -> io::Result<()> {
-        while let Some(event) = self.iter.next() {
+        if let Some((name, class)) = self.config.wrapper {
+            self.write("<")?;
+            escape_html(&mut self.writer, name)?;
+            if !class.is_empty() {
+                self.write(" class=\"")?;
+                escape_html(&mut self.writer, class)?;
+                self.write("\"")?;
+            }
+            self.write(">\n")?;
+        }
+        while let Some(event) = self.next_event() {
+            if self.config.collapse_breaks {
+                if let SoftBreak | HardBreak = event {
+                    let hard = matches!(event, HardBreak) || self.pending_break == Some(true);
+                    self.pending_break = Some(hard);
+                    continue;
+                }
+                self.flush_break()?;
+            }
             match event {
+                Start(Tag::Paragraph) | Start(Tag::Heading(..)) | Start(Tag::TableCell) => {
+                    self.in_inline_block = true
+                }
+                End(Tag::Paragraph) | End(Tag::Heading(..)) | End(Tag::TableCell) => {
+                    self.in_inline_block = false
+                }
+                _ => {}
+            }
+            let collapses_after = !matches!(
+                event,
+                Text(_) | Start(Tag::Heading(..)) | End(Tag::Heading(..))
+            );
+            match event {
+                Start(Tag::Paragraph) if self.config.figures => {
+                    if let Some(caption) = self.lone_titled_image() {
+                        self.write_figure(caption)?;
+                        // The end of the paragraph has been consumed.
+                        self.in_inline_block = false;
+                    } else {
+                        self.start_tag(Tag::Paragraph)?;
+                    }
+                }
                 Start(tag) => {
                     self.start_tag(tag)?;
                 }
                 End(tag) => {
                     self.end_tag(tag)?;
                 }
-                Text(text) => {
-                    escape_html(&mut self.writer, &text)?;
-                    self.end_newline = text.ends_with('\n');
+                Text(mut text) => {
+                    if self.config.strip_control_chars && text.chars().any(is_stripped_control) {
+                        text = text.replace(is_stripped_control, "").into();
+                    }
+                    if let Some(width) = self.config.expand_tabs {
+                        if !self.in_code_block && text.contains('\t') {
+                            text = text.replace('\t', &" ".repeat(width)).into();
+                        }
+                    }
+                    if self.in_obfuscated_link {
+                        self.write_obfuscated(&text)?;
+                    } else if let Some(after_space) = self.heading_space {
+                        self.write_heading_text(&text, after_space)?;
+                    } else {
+                        escape_html(&mut self.writer, &text)?;
+                        self.end_newline = text.ends_with('\n');
+                    }
                 }
                 Code(text) => {
                     self.write("<code>")?;
-                    escape_html(&mut self.writer, &text)?;
+                    self.write_code_span(&text)?;
                     self.write("</code>")?;
                 }
                 Html(html) => {
-                    self.write(&html)?;
+                    let kind = if self.in_inline_block {
+                        HtmlKind::Inline
+                    } else {
+                        HtmlKind::Block
+                    };
+                    self.write_raw_html(kind, &html)?;
+                }
+                InlineHtml(_, html) => {
+                    self.write_raw_html(HtmlKind::Inline, &html)?;
                 }
                 SoftBreak => {
                     self.write_newline()?;
@@ -111,174 +632,312 @@ where
                     self.write("<br />\n")?;
                 }
                 Rule => {
-                    if self.end_newline {
-                        self.write("<hr />\n")?;
-                    } else {
-                        self.write("\n<hr />\n")?;
+                    if !self.end_newline {
+                        self.write_newline()?;
                     }
+                    self.write("<hr")?;
+                    self.write_sourcepos()?;
+                    self.write(" />\n")?;
                 }
                 FootnoteReference(name) => {
                     let len = self.numbers.len() + 1;
-                    self.write("<sup class=\"footnote-reference\"><a href=\"#")?;
+                    self.write("<sup class=\"footnote-reference\"")?;
+                    if self.config.footnote_backrefs {
+                        let refs = self.footnote_refs.entry(name.clone()).or_insert(0);
+                        *refs += 1;
+                        let refs = *refs;
+                        self.write(" id=\"fnref-")?;
+                        escape_html(&mut self.writer, &name)?;
+                        if refs > 1 {
+                            write!(&mut self.writer, "-{}", refs)?;
+                        }
+                        self.write("\"")?;
+                    }
+                    self.write("><a href=\"#")?;
                     escape_html(&mut self.writer, &name)?;
                     self.write("\">")?;
                     let number = *self.numbers.entry(name).or_insert(len);
                     write!(&mut self.writer, "{}", number)?;
                     self.write("</a></sup>")?;
                 }
-                TaskListMarker(true) => {
-                    self.write("<input disabled=\"\" type=\"checkbox\" checked=\"\"/>\n")?;
-                }
-                TaskListMarker(false) => {
-                    self.write("<input disabled=\"\" type=\"checkbox\"/>\n")?;
+                TaskListMarker(checked) => {
+                    self.write("<input disabled=\"\" type=\"checkbox\"")?;
+                    if self.config.task_list_ids {
+                        write!(
+                            &mut self.writer,
+                            " data-task-id=\"task-{}\"",
+                            self.task_count
+                        )?;
+                    }
+                    if checked {
+                        self.write(" checked=\"\"")?;
+                    }
+                    self.write("/>\n")?;
                 }
             }
+            // Spaces after inline code, raw HTML or a tag still separate
+            // words, so they collapse to one rather than being dropped.
+            if collapses_after && self.heading_space.is_some() {
+                self.heading_space = Some(false);
+            }
+        }
+        self.flush_break()?;
+        if let Some((name, _)) = self.config.wrapper {
+            if !self.end_newline {
+                self.write_newline()?;
+            }
+            self.write("</")?;
+            escape_html(&mut self.writer, name)?;
+            self.write(">\n")?;
         }
         Ok(())
     }
 
     /// Writes the start of an HTML tag.
     fn start_tag(&mut self, tag: Tag<'a>) -> io::Result<()> {
+        let attrs = match self.config.tag_attributes {
+            Some(ref mut callback) => callback(&tag),
+            None => Vec::new(),
+        };
         match tag {
             Tag::Paragraph => {
-                if self.end_newline {
-                    self.write("<p>")
-                } else {
-                    self.write("\n<p>")
+                if !self.end_newline {
+                    self.write_newline()?;
                 }
+                self.write("<p")?;
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
             }
-            Tag::Heading(level) => {
-                if self.end_newline {
-                    self.end_newline = false;
-                    write!(&mut self.writer, "<{}>", level)
-                } else {
-                    write!(&mut self.writer, "\n<{}>", level)
+            Tag::Heading(level, id, classes) => {
+                if self.config.collapse_heading_whitespace {
+                    self.heading_space = Some(false);
+                }
+                if !self.end_newline {
+                    self.write_newline()?;
+                }
+                write!(&mut self.writer, "<{}", level)?;
+                if let Some(id) = id {
+                    self.write_attr(AttrName::Id, &id)?;
                 }
+                if !classes.is_empty() {
+                    self.write_attr(AttrName::Class, &classes.join(" "))?;
+                }
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">")?;
+                if self.config.heading_numbers {
+                    self.write_heading_number(level)?;
+                }
+                Ok(())
             }
             Tag::Table(alignments) => {
                 self.table_alignments = alignments;
-                self.write("<table>")
+                self.write("<table")?;
+                if self.config.table_roles {
+                    self.write(" role=\"table\"")?;
+                }
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">")?;
+                if self.config.table_colgroups {
+                    self.write("<colgroup>")?;
+                    for i in 0..self.table_alignments.len() {
+                        let class = match self.table_alignments[i] {
+                            Alignment::None => "",
+                            Alignment::Left => " class=\"align-left\"",
+                            Alignment::Center => " class=\"align-center\"",
+                            Alignment::Right => " class=\"align-right\"",
+                        };
+                        write!(&mut self.writer, "<col{} />", class)?;
+                    }
+                    self.write("</colgroup>")?;
+                }
+                Ok(())
             }
             Tag::TableHead => {
                 self.table_state = TableState::Head;
                 self.table_cell_index = 0;
-                self.write("<thead><tr>")
+                self.write("<thead><tr")?;
+                if self.config.table_roles {
+                    self.write(" role=\"row\"")?;
+                }
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
             }
             Tag::TableRow => {
                 self.table_cell_index = 0;
-                self.write("<tr>")
+                self.write("<tr")?;
+                if self.config.table_roles {
+                    self.write(" role=\"row\"")?;
+                }
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
             }
             Tag::TableCell => {
                 match self.table_state {
                     TableState::Head => {
                         self.write("<th")?;
+                        if self.config.table_roles {
+                            self.write(" scope=\"col\"")?;
+                        }
                     }
                     TableState::Body => {
                         self.write("<td")?;
                     }
                 }
-                match self.table_alignments.get(self.table_cell_index) {
-                    Some(&Alignment::Left) => self.write(" align=\"left\">"),
-                    Some(&Alignment::Center) => self.write(" align=\"center\">"),
-                    Some(&Alignment::Right) => self.write(" align=\"right\">"),
-                    _ => self.write(">"),
+                self.write_attrs(&attrs)?;
+                let align = match self.table_alignments.get(self.table_cell_index) {
+                    Some(&Alignment::Left) => "left",
+                    Some(&Alignment::Center) => "center",
+                    Some(&Alignment::Right) => "right",
+                    _ => return self.write(">"),
+                };
+                if self.config.inline_alignment_styles {
+                    write!(&mut self.writer, " style=\"text-align: {}\">", align)
+                } else {
+                    write!(&mut self.writer, " align=\"{}\">", align)
                 }
             }
             Tag::BlockQuote => {
-                if self.end_newline {
-                    self.write("<blockquote>\n")
-                } else {
-                    self.write("\n<blockquote>\n")
+                if !self.end_newline {
+                    self.write_newline()?;
                 }
+                self.write("<blockquote")?;
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">\n")
             }
             Tag::CodeBlock(info) => {
+                self.in_code_block = true;
                 if !self.end_newline {
                     self.write_newline()?;
                 }
-                match info {
-                    CodeBlockKind::Fenced(info) => {
-                        let lang = info.split(' ').next().unwrap();
-                        if lang.is_empty() {
-                            self.write("<pre><code>")
-                        } else {
-                            self.write("<pre><code class=\"language-")?;
-                            escape_html(&mut self.writer, lang)?;
-                            self.write("\">")
-                        }
+                self.write("<pre")?;
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write("><code")?;
+                if let CodeBlockKind::Fenced(info) = info {
+                    let lang = info.split(' ').next().unwrap();
+                    let lang = match self.config.language_callback {
+                        Some(ref mut callback) if !lang.is_empty() => callback(lang),
+                        _ => lang.into(),
+                    };
+                    if !lang.is_empty() {
+                        self.write_attr(AttrName::Class, &format!("language-{}", lang))?;
                     }
-                    CodeBlockKind::Indented => self.write("<pre><code>"),
-                }
-            }
-            Tag::List(Some(1)) => {
-                if self.end_newline {
-                    self.write("<ol>\n")
-                } else {
-                    self.write("\n<ol>\n")
                 }
+                self.write(">")
             }
             Tag::List(Some(start)) => {
-                if self.end_newline {
-                    self.write("<ol start=\"")?;
-                } else {
-                    self.write("\n<ol start=\"")?;
+                if !self.end_newline {
+                    self.write_newline()?;
                 }
-                write!(&mut self.writer, "{}", start)?;
-                self.write("\">\n")
+                self.write("<ol")?;
+                if start != 1 {
+                    write!(&mut self.writer, " start=\"{}\"", start)?;
+                }
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">\n")
             }
             Tag::List(None) => {
-                if self.end_newline {
-                    self.write("<ul>\n")
-                } else {
-                    self.write("\n<ul>\n")
+                if !self.end_newline {
+                    self.write_newline()?;
                 }
+                self.write("<ul")?;
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">\n")
             }
             Tag::Item => {
-                if self.end_newline {
-                    self.write("<li>")
-                } else {
-                    self.write("\n<li>")
+                if !self.end_newline {
+                    self.write_newline()?;
                 }
-            }
-            Tag::Emphasis => self.write("<em>"),
-            Tag::Strong => self.write("<strong>"),
-            Tag::Strikethrough => self.write("<del>"),
-            Tag::Link(LinkType::Email, dest, title) => {
-                self.write("<a href=\"mailto:")?;
-                escape_href(&mut self.writer, &dest)?;
-                if !title.is_empty() {
-                    self.write("\" title=\"")?;
-                    escape_html(&mut self.writer, &title)?;
+                self.write("<li")?;
+                if self.config.task_list_ids {
+                    if let Some(item) = self.next_item() {
+                        let is_task = matches!(item.0, TaskListMarker(_));
+                        self.lookahead.push_front(item);
+                        if is_task {
+                            self.task_count += 1;
+                            write!(&mut self.writer, " id=\"task-{}\"", self.task_count)?;
+                        }
+                    }
                 }
-                self.write("\">")
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
             }
-            Tag::Link(_link_type, dest, title) => {
-                self.write("<a href=\"")?;
-                escape_href(&mut self.writer, &dest)?;
+            Tag::Emphasis => {
+                self.write("<em")?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
+            }
+            Tag::Strong => {
+                self.write("<strong")?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
+            }
+            Tag::Strikethrough => {
+                self.write("<del")?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
+            }
+            Tag::ExtraEmphasis => {
+                self.write("<em")?;
+                self.write_attrs(&attrs)?;
+                self.write(">")
+            }
+            Tag::Link(link_type, dest, title, _) => {
+                let dest = if link_type == LinkType::Email {
+                    format!("mailto:{}", dest).into()
+                } else {
+                    dest
+                };
+                let obfuscate = self.config.obfuscate_emails
+                    && matches!(dest.get(..7), Some(scheme) if scheme.eq_ignore_ascii_case("mailto:"));
+                self.write("<a")?;
+                if obfuscate {
+                    if let Some(href) = self.attr_value(AttrName::Href, &dest) {
+                        self.write(" href=\"")?;
+                        self.write_obfuscated(&href)?;
+                        self.write("\"")?;
+                    }
+                } else {
+                    self.write_attr(AttrName::Href, &dest)?;
+                }
                 if !title.is_empty() {
-                    self.write("\" title=\"")?;
-                    escape_html(&mut self.writer, &title)?;
+                    self.write_attr(AttrName::Title, &title)?;
                 }
-                self.write("\">")
+                self.write_attrs(&attrs)?;
+                self.in_obfuscated_link = obfuscate;
+                self.write(">")
             }
             Tag::Image(_link_type, dest, title) => {
-                self.write("<img src=\"")?;
-                escape_href(&mut self.writer, &dest)?;
-                self.write("\" alt=\"")?;
+                self.write("<img")?;
+                self.write_attr(AttrName::Src, &dest)?;
+                self.write(" alt=\"")?;
                 self.raw_text()?;
+                self.write("\"")?;
                 if !title.is_empty() {
-                    self.write("\" title=\"")?;
-                    escape_html(&mut self.writer, &title)?;
+                    self.write_attr(AttrName::Title, &title)?;
                 }
-                self.write("\" />")
+                self.write_attrs(&attrs)?;
+                self.write(" />")
             }
             Tag::FootnoteDefinition(name) => {
-                if self.end_newline {
-                    self.write("<div class=\"footnote-definition\" id=\"")?;
-                } else {
-                    self.write("\n<div class=\"footnote-definition\" id=\"")?;
+                if !self.end_newline {
+                    self.write_newline()?;
                 }
+                self.write("<div class=\"footnote-definition\" id=\"")?;
                 escape_html(&mut self.writer, &*name)?;
-                self.write("\"><sup class=\"footnote-definition-label\">")?;
+                self.write("\"")?;
+                self.write_sourcepos()?;
+                self.write_attrs(&attrs)?;
+                self.write("><sup class=\"footnote-definition-label\">")?;
                 let len = self.numbers.len() + 1;
                 let number = *self.numbers.entry(name).or_insert(len);
                 write!(&mut self.writer, "{}", number)?;
@@ -292,7 +951,8 @@ where
             Tag::Paragraph => {
                 self.write("</p>\n")?;
             }
-            Tag::Heading(level) => {
+            Tag::Heading(level, ..) => {
+                self.heading_space = None;
                 self.write("</")?;
                 write!(&mut self.writer, "{}", level)?;
                 self.write(">\n")?;
@@ -322,6 +982,7 @@ where
                 self.write("</blockquote>\n")?;
             }
             Tag::CodeBlock(_) => {
+                self.in_code_block = false;
                 self.write("</code></pre>\n")?;
             }
             Tag::List(Some(_)) => {
@@ -342,11 +1003,34 @@ where
             Tag::Strikethrough => {
                 self.write("</del>")?;
             }
-            Tag::Link(_, _, _) => {
+            Tag::ExtraEmphasis => {
+                self.write("</em>")?;
+            }
+            Tag::Link(..) => {
+                self.in_obfuscated_link = false;
                 self.write("</a>")?;
             }
             Tag::Image(_, _, _) => (), // shouldn't happen, handled in start
-            Tag::FootnoteDefinition(_) => {
+            Tag::FootnoteDefinition(name) => {
+                let refs = self.footnote_refs.get(&name).copied().unwrap_or(0);
+                if self.config.footnote_backrefs && refs > 0 {
+                    for i in 1..=refs {
+                        if i > 1 {
+                            self.write(" ")?;
+                        }
+                        self.write("<a href=\"#fnref-")?;
+                        escape_html(&mut self.writer, &name)?;
+                        if i > 1 {
+                            write!(&mut self.writer, "-{}", i)?;
+                        }
+                        self.write("\" class=\"footnote-backref\">↩")?;
+                        if refs > 1 {
+                            write!(&mut self.writer, "{}", i)?;
+                        }
+                        self.write("</a>")?;
+                    }
+                    self.write_newline()?;
+                }
                 self.write("</div>\n")?;
             }
         }
@@ -356,7 +1040,7 @@ where
     // run raw text, consuming end tag
     fn raw_text(&mut self) -> io::Result<()> {
         let mut nest = 0;
-        while let Some(event) = self.iter.next() {
+        while let Some(event) = self.next_event() {
             match event {
                 Start(_) => nest += 1,
                 End(_) => {
@@ -365,7 +1049,7 @@ where
                     }
                     nest -= 1;
                 }
-                Html(text) | Code(text) | Text(text) => {
+                Html(text) | InlineHtml(_, text) | Code(text) | Text(text) => {
                     escape_html(&mut self.writer, &text)?;
                     self.end_newline = text.ends_with('\n');
                 }
@@ -414,9 +1098,37 @@ where
 /// ```
 pub fn push_html<'a, I>(s: &mut String, iter: I)
 where
-    I: Iterator<Item = Event<'a>>,
+    I: IntoIterator<Item = Event<'a>>,
+{
+    push_html_ext(s, iter, HtmlConfig::default());
+}
+
+/// Like [`push_html`], but renders according to the given configuration.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{html, Options, Parser};
+///
+/// let markdown_str = "a | b\n--|--\n1 | 2";
+/// let parser = Parser::new_ext(markdown_str, Options::ENABLE_TABLES);
+/// let config = html::HtmlConfig {
+///     table_roles: true,
+///     ..Default::default()
+/// };
+///
+/// let mut html_buf = String::new();
+/// html::push_html_ext(&mut html_buf, parser, config);
+///
+/// assert!(html_buf.starts_with("<table role=\"table\"><thead><tr role=\"row\"><th scope=\"col\">a</th>"));
+/// ```
+pub fn push_html_ext<'a, I>(s: &mut String, iter: I, config: HtmlConfig<'_>)
+where
+    I: IntoIterator<Item = Event<'a>>,
 {
-    HtmlWriter::new(iter, s).run().unwrap();
+    HtmlWriter::new(iter.into_iter().map(|event| (event, None)), s, config)
+        .run()
+        .unwrap();
 }
 
 /// Iterate over an `Iterator` of `Event`s, generate HTML for each `Event`, and
@@ -454,8 +1166,107 @@ where
 /// ```
 pub fn write_html<'a, I, W>(writer: W, iter: I) -> io::Result<()>
 where
-    I: Iterator<Item = Event<'a>>,
+    I: IntoIterator<Item = Event<'a>>,
     W: Write,
 {
-    HtmlWriter::new(iter, WriteWrapper(writer)).run()
+    write_html_ext(writer, iter, HtmlConfig::default())
+}
+
+/// Like [`write_html`], but renders according to the given configuration.
+pub fn write_html_ext<'a, I, W>(writer: W, iter: I, config: HtmlConfig<'_>) -> io::Result<()>
+where
+    I: IntoIterator<Item = Event<'a>>,
+    W: Write,
+{
+    HtmlWriter::new(
+        iter.into_iter().map(|event| (event, None)),
+        WriteWrapper(writer),
+        config,
+    )
+    .run()
+}
+
+/// Like [`push_html_ext`], but takes events with their source ranges, as
+/// produced by [`Parser::into_offset_iter`](../struct.Parser.html#method.into_offset_iter),
+/// along with the source text. Block elements get a `data-sourcepos`
+/// attribute with the lines and columns they span, such as `3:1-5:10`.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{html, Parser};
+///
+/// let markdown_str = "# hello\n\n* alpha\n";
+/// let parser = Parser::new(markdown_str).into_offset_iter();
+///
+/// let mut html_buf = String::new();
+/// html::push_html_with_sourcepos(&mut html_buf, markdown_str, parser, Default::default());
+///
+/// assert_eq!(html_buf, r#"<h1 data-sourcepos="1:1-1:7">hello</h1>
+/// <ul data-sourcepos="3:1-3:7">
+/// <li data-sourcepos="3:1-3:7">alpha</li>
+/// </ul>
+/// "#);
+/// ```
+pub fn push_html_with_sourcepos<'a, I>(
+    s: &mut String,
+    source: &str,
+    iter: I,
+    config: HtmlConfig<'_>,
+) where
+    I: IntoIterator<Item = (Event<'a>, Range<usize>)>,
+{
+    let mut writer = HtmlWriter::new(with_trimmed_ranges(source, iter.into_iter()), s, config);
+    writer.line_starts = line_starts(source);
+    writer.run().unwrap();
+}
+
+/// Like [`push_html_with_sourcepos`], but writes to a writable stream.
+pub fn write_html_with_sourcepos<'a, I, W>(
+    writer: W,
+    source: &str,
+    iter: I,
+    config: HtmlConfig<'_>,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = (Event<'a>, Range<usize>)>,
+    W: Write,
+{
+    let mut writer = HtmlWriter::new(
+        with_trimmed_ranges(source, iter.into_iter()),
+        WriteWrapper(writer),
+        config,
+    );
+    writer.line_starts = line_starts(source);
+    writer.run()
+}
+
+/// Removes trailing line endings from the source ranges, so they end at the
+/// last character of the element.
+fn with_trimmed_ranges<'a, 's, I>(
+    source: &'s str,
+    iter: I,
+) -> impl Iterator<Item = (Event<'a>, Option<Range<usize>>)> + 's
+where
+    I: Iterator<Item = (Event<'a>, Range<usize>)> + 's,
+{
+    let bytes = source.as_bytes();
+    iter.map(move |(event, mut range)| {
+        while range.end > range.start && matches!(bytes[range.end - 1], b'\n' | b'\r') {
+            range.end -= 1;
+        }
+        (event, Some(range))
+    })
+}
+
+/// Returns the start offsets of all lines in the text.
+fn line_starts(text: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut starts = vec![0];
+    for (ix, &b) in bytes.iter().enumerate() {
+        if b == b'\n' || (b == b'\r' && bytes.get(ix + 1) != Some(&b'\n')) {
+            starts.push(ix + 1);
+        }
+    }
+    starts
 }
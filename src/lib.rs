@@ -46,6 +46,30 @@
 //! let expected_html = "<p>Hello world, this is a <del>complicated</del> <em>very simple</em> example.</p>\n";
 //! assert_eq!(expected_html, &html_output);
 //! ```
+//!
+//! # Reusing events
+//!
+//! The renderers and helpers of this crate take anything that can be turned
+//! into an iterator of events. To render a document more than once, such as
+//! to HTML and to an outline, collect the events and pass a cloning iterator
+//! over them to each, instead of parsing the text again. Cloning events is
+//! cheap, since the text they contain is borrowed from the source. Collect
+//! them with [`Event::into_static`] to keep them after the source is gone.
+//!
+//! ```rust
+//! use pulldown_cmark::{html, text, Event, Parser};
+//!
+//! let events: Vec<Event<'static>> = Parser::new("# Title\n\nSome *words*.")
+//!     .map(Event::into_static)
+//!     .collect();
+//!
+//! let mut html_output = String::new();
+//! html::push_html(&mut html_output, events.iter().cloned());
+//! let count = text::word_count(events.iter().cloned(), &Default::default());
+//!
+//! assert_eq!("<h1>Title</h1>\n<p>Some <em>words</em>.</p>\n", html_output);
+//! assert_eq!(3, count.words);
+//! ```
 
 // When compiled for the rustc compiler itself we want to make sure that this is
 // an unstable crate.
@@ -60,19 +84,35 @@ use serde::{Deserialize, Serialize};
 
 pub mod html;
 
+#[cfg(feature = "serde")]
+pub mod ast;
+mod builder;
+mod context;
+pub mod debug;
 mod entities;
 pub mod escape;
 mod firstpass;
+pub mod headings;
 mod linklabel;
 mod parse;
 mod puncttable;
 mod scanners;
 mod strings;
+pub mod text;
 mod tree;
 
 use std::{convert::TryFrom, fmt::Display};
 
-pub use crate::parse::{BrokenLink, BrokenLinkCallback, LinkDef, OffsetIter, Parser, RefDefs};
+pub use crate::builder::EventBuilder;
+pub use crate::context::ContextIter;
+#[cfg(feature = "delimiter-runs")]
+pub use crate::parse::DelimiterRun;
+#[cfg(feature = "stats")]
+pub use crate::parse::ParseStats;
+pub use crate::parse::{
+    code_span_raw_range, BlockBoundaryIter, BlockItem, BrokenLink, BrokenLinkCallback, LinkDef,
+    LinkInfo, LinkIter, OffsetIter, ParsedBlocks, Parser, ParserConfig, RefDefs,
+};
 pub use crate::strings::{CowStr, InlineStr};
 
 /// Codeblock kind.
@@ -93,6 +133,14 @@ impl<'a> CodeBlockKind<'a> {
     pub fn is_fenced(&self) -> bool {
         matches!(*self, CodeBlockKind::Fenced(_))
     }
+
+    /// Converts into a `CodeBlockKind` that doesn't borrow from the source.
+    pub fn into_static(self) -> CodeBlockKind<'static> {
+        match self {
+            CodeBlockKind::Indented => CodeBlockKind::Indented,
+            CodeBlockKind::Fenced(info) => CodeBlockKind::Fenced(info.into_static()),
+        }
+    }
 }
 
 /// Tags for elements that can contain other elements.
@@ -102,8 +150,11 @@ pub enum Tag<'a> {
     /// A paragraph of text and other inline elements.
     Paragraph,
 
-    /// A heading. The field indicates the level of the heading.
-    Heading(HeadingLevel),
+    /// A heading. The fields are the level of the heading, and the id and
+    /// classes given in a trailing attribute block such as `{#id .class}`.
+    /// Attribute blocks are only recognized with
+    /// [`Options::ENABLE_HEADING_ATTRIBUTES`](struct.Options.html#associatedconstant.ENABLE_HEADING_ATTRIBUTES).
+    Heading(HeadingLevel, Option<CowStr<'a>>, Vec<CowStr<'a>>),
 
     BlockQuote,
     /// A code block.
@@ -132,14 +183,56 @@ pub enum Tag<'a> {
     Emphasis,
     Strong,
     Strikethrough,
+    /// Text delimited by the doubled character set as
+    /// [`ParserConfig::extra_emphasis_delimiter`], such as `//text//`.
+    ExtraEmphasis,
 
     /// A link. The first field is the link type, the second the destination URL and the third is a title.
-    Link(LinkType, CowStr<'a>, CowStr<'a>),
+    /// The fourth field is the label of the reference the link was resolved through, normalized by
+    /// trimming it and collapsing runs of whitespace inside it to a single space. Its case is kept
+    /// as written in the source. It is `None` for inline links and autolinks.
+    Link(LinkType, CowStr<'a>, CowStr<'a>, Option<CowStr<'a>>),
 
     /// An image. The first field is the link type, the second the destination URL and the third is a title.
     Image(LinkType, CowStr<'a>, CowStr<'a>),
 }
 
+impl<'a> Tag<'a> {
+    /// Converts into a `Tag` that doesn't borrow from the source.
+    pub fn into_static(self) -> Tag<'static> {
+        match self {
+            Tag::Paragraph => Tag::Paragraph,
+            Tag::Heading(level, id, classes) => Tag::Heading(
+                level,
+                id.map(CowStr::into_static),
+                classes.into_iter().map(CowStr::into_static).collect(),
+            ),
+            Tag::BlockQuote => Tag::BlockQuote,
+            Tag::CodeBlock(kind) => Tag::CodeBlock(kind.into_static()),
+            Tag::List(start) => Tag::List(start),
+            Tag::Item => Tag::Item,
+            Tag::FootnoteDefinition(name) => Tag::FootnoteDefinition(name.into_static()),
+            Tag::Table(alignments) => Tag::Table(alignments),
+            Tag::TableHead => Tag::TableHead,
+            Tag::TableRow => Tag::TableRow,
+            Tag::TableCell => Tag::TableCell,
+            Tag::Emphasis => Tag::Emphasis,
+            Tag::Strong => Tag::Strong,
+            Tag::Strikethrough => Tag::Strikethrough,
+            Tag::ExtraEmphasis => Tag::ExtraEmphasis,
+            Tag::Link(link_type, dest, title, label) => Tag::Link(
+                link_type,
+                dest.into_static(),
+                title.into_static(),
+                label.map(CowStr::into_static),
+            ),
+            Tag::Image(link_type, dest, title) => {
+                Tag::Image(link_type, dest.into_static(), title.into_static())
+            }
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HeadingLevel {
@@ -234,7 +327,11 @@ pub enum Event<'a> {
     /// End of a tagged element.
     #[cfg_attr(feature = "serde", serde(borrow))]
     End(Tag<'a>),
-    /// A text node.
+    /// A text node. Text is always literal: HTML recognized by the parser is
+    /// reported as `Html` or `InlineHtml` events instead, and entity and
+    /// character references are already resolved. A `<` or `&` in text is
+    /// meant to be shown as is, and renderers escape it as their output
+    /// format requires.
     #[cfg_attr(feature = "serde", serde(borrow))]
     Text(CowStr<'a>),
     /// An inline code node.
@@ -243,6 +340,13 @@ pub enum Event<'a> {
     /// An HTML node.
     #[cfg_attr(feature = "serde", serde(borrow))]
     Html(CowStr<'a>),
+    /// A piece of inline HTML, along with its kind. Only emitted when
+    /// [`Options::ENABLE_INLINE_HTML_KINDS`] is set; inline HTML is reported
+    /// as `Html` events otherwise.
+    InlineHtml(
+        InlineHtmlKind,
+        #[cfg_attr(feature = "serde", serde(borrow))] CowStr<'a>,
+    ),
     /// A reference to a footnote with given label, which may or may not be defined
     /// by an event with a `Tag::FootnoteDefinition` tag. Definitions and references to them may
     /// occur in any order.
@@ -258,6 +362,46 @@ pub enum Event<'a> {
     TaskListMarker(bool),
 }
 
+impl<'a> Event<'a> {
+    /// Converts into an `Event` that doesn't borrow from the source, so that
+    /// it can outlive it or be sent to another thread.
+    pub fn into_static(self) -> Event<'static> {
+        match self {
+            Event::Start(tag) => Event::Start(tag.into_static()),
+            Event::End(tag) => Event::End(tag.into_static()),
+            Event::Text(text) => Event::Text(text.into_static()),
+            Event::Code(text) => Event::Code(text.into_static()),
+            Event::Html(html) => Event::Html(html.into_static()),
+            Event::InlineHtml(kind, html) => Event::InlineHtml(kind, html.into_static()),
+            Event::FootnoteReference(name) => Event::FootnoteReference(name.into_static()),
+            Event::SoftBreak => Event::SoftBreak,
+            Event::HardBreak => Event::HardBreak,
+            Event::Rule => Event::Rule,
+            Event::TaskListMarker(checked) => Event::TaskListMarker(checked),
+        }
+    }
+}
+
+/// Kind of a piece of inline HTML, as reported by [`Event::InlineHtml`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InlineHtmlKind {
+    /// An opening tag like `<b>`.
+    OpenTag,
+    /// A closing tag like `</b>`.
+    CloseTag,
+    /// A self-closing tag like `<br/>`.
+    SelfClosingTag,
+    /// A comment like `<!-- c -->`.
+    Comment,
+    /// A processing instruction like `<?php ?>`.
+    ProcessingInstruction,
+    /// A declaration like `<!DOCTYPE html>`.
+    Declaration,
+    /// A CDATA section like `<![CDATA[x]]>`.
+    CData,
+}
+
 /// Table column text alignment.
 #[derive(Copy, Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -279,5 +423,47 @@ bitflags::bitflags! {
         const ENABLE_STRIKETHROUGH = 1 << 3;
         const ENABLE_TASKLISTS = 1 << 4;
         const ENABLE_SMART_PUNCTUATION = 1 << 5;
+        /// Don't recognize `~~~` code fences; such lines become regular paragraph text.
+        const DISABLE_TILDE_FENCES = 1 << 6;
+        /// Don't recognize ```` ``` ```` code fences; such lines become regular paragraph text.
+        const DISABLE_BACKTICK_FENCES = 1 << 7;
+        /// Join a line ending with a backslash to the next line of the paragraph,
+        /// instead of producing a hard break. The backslash and line ending are
+        /// removed. Hard breaks from trailing spaces are not affected.
+        const ENABLE_LINE_CONTINUATIONS = 1 << 8;
+        /// Don't recognize autolinks such as `<https://example.com>`. The angle
+        /// brackets are then only considered for inline HTML.
+        const DISABLE_AUTOLINKS = 1 << 9;
+        /// Handle whitespace in code spans as CommonMark 0.28 did: strip all
+        /// leading and trailing spaces and line endings, and collapse interior
+        /// runs of them to a single space. By default, as of CommonMark 0.29,
+        /// only a single space is stripped from both ends, and only if there is
+        /// one at both ends.
+        const ENABLE_OLD_CODE_SPANS = 1 << 10;
+        /// Report inline HTML as [`Event::InlineHtml`] events, which carry the
+        /// kind of HTML, instead of as `Html` events.
+        const ENABLE_INLINE_HTML_KINDS = 1 << 11;
+        /// Leave out paragraphs consisting of nothing but whitespace, such as
+        /// non-breaking spaces, and line breaks. Whitespace written as entity
+        /// references, like `&nbsp;`, keeps a paragraph.
+        const OMIT_BLANK_PARAGRAPHS = 1 << 12;
+        /// Don't recognize ATX headings without content, such as a line of
+        /// just `#`. Such lines are then paragraph text.
+        const DISABLE_EMPTY_ATX_HEADINGS = 1 << 13;
+        /// Only make the last line of a paragraph a setext heading when it is
+        /// followed by a setext underline. Preceding lines remain a paragraph,
+        /// where CommonMark would make the whole paragraph the heading.
+        const ENABLE_SINGLE_LINE_SETEXT_HEADINGS = 1 << 14;
+        /// Match reference links to definitions case sensitively, so that
+        /// `[Foo]` and `[foo]` refer to different definitions. CommonMark
+        /// matches labels case insensitively.
+        const ENABLE_CASE_SENSITIVE_REFERENCES = 1 << 15;
+        /// Parse a trailing attribute block such as `{#id .class1 .class2}`
+        /// on headings, giving the heading an id and classes.
+        const ENABLE_HEADING_ATTRIBUTES = 1 << 16;
+        /// Turn URLs starting with `http://`, `https://` or `www.` and email
+        /// addresses into links without angle brackets, as in GitHub Flavored
+        /// Markdown. They are left as text inside the text of other links.
+        const ENABLE_GFM_AUTOLINKS = 1 << 17;
     }
 }
@@ -30,7 +30,33 @@ pub(crate) enum ReferenceLabel<'a> {
     Footnote(CowStr<'a>),
 }
 
-pub(crate) type LinkLabel<'a> = UniCase<CowStr<'a>>;
+/// Label of a reference definition, as used to look it up.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) enum LinkLabel<'a> {
+    /// Matched using unicode case folding, as the spec requires.
+    Folded(UniCase<CowStr<'a>>),
+    /// Matched exactly, see `Options::ENABLE_CASE_SENSITIVE_REFERENCES`.
+    Exact(CowStr<'a>),
+}
+
+impl<'a> LinkLabel<'a> {
+    pub(crate) fn new(label: CowStr<'a>, case_sensitive: bool) -> Self {
+        if case_sensitive {
+            LinkLabel::Exact(label)
+        } else {
+            LinkLabel::Folded(UniCase::new(label))
+        }
+    }
+}
+
+impl<'a> AsRef<str> for LinkLabel<'a> {
+    fn as_ref(&self) -> &str {
+        match self {
+            LinkLabel::Folded(label) => label.as_ref(),
+            LinkLabel::Exact(label) => label.as_ref(),
+        }
+    }
+}
 
 /// Assumes the opening bracket has already been scanned.
 /// The line break handler determines what happens when a linebreak
@@ -104,16 +130,25 @@ pub(crate) fn scan_link_label_rest<'t>(
     if only_white_space {
         None
     } else {
+        // normalize labels by stripping leading and trailing whitespace
         let cow = if mark == 0 {
-            text[..ix].into()
+            trim_whitespace(&text[..ix]).into()
         } else {
             label.push_str(&text[mark..ix]);
-            label.into()
+            if trim_whitespace(&label).len() == label.len() {
+                label.into()
+            } else {
+                trim_whitespace(&label).to_owned().into()
+            }
         };
         Some((ix + 1, cow))
     }
 }
 
+fn trim_whitespace(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_ascii() && is_ascii_whitespace(c as u8))
+}
+
 #[cfg(test)]
 mod test {
     use super::scan_link_label_rest;
@@ -127,6 +162,16 @@ mod test {
         assert_eq!(expected_output, normalized_label.as_ref());
     }
 
+    #[test]
+    fn leading_and_trailing_whitespace_stripped() {
+        let (bytes, label) = scan_link_label_rest(" foo ]", &|_| None).unwrap();
+        assert_eq!(6, bytes);
+        assert_eq!("foo", label.as_ref());
+
+        let (_bytes, label) = scan_link_label_rest("\t\tfoo  bar\n]", &|_| Some(0)).unwrap();
+        assert_eq!("foo bar", label.as_ref());
+    }
+
     #[test]
     fn return_carriage_linefeed_ok() {
         let input = "hello\r\nworld\r\n]";
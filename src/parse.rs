@@ -23,16 +23,18 @@
 use std::cmp::{max, min};
 use std::collections::{HashMap, VecDeque};
 use std::iter::FusedIterator;
+use std::num::NonZeroUsize;
 use std::ops::{Index, Range};
-
-use unicase::UniCase;
+use std::sync::mpsc::{SendError, Sender};
 
 use crate::firstpass::run_first_pass;
 use crate::linklabel::{scan_link_label_rest, LinkLabel, ReferenceLabel};
 use crate::scanners::*;
 use crate::strings::CowStr;
 use crate::tree::{Tree, TreeIndex};
-use crate::{Alignment, CodeBlockKind, Event, HeadingLevel, LinkType, Options, Tag};
+use crate::{
+    Alignment, CodeBlockKind, Event, HeadingLevel, InlineHtmlKind, LinkType, Options, Tag,
+};
 
 // Allowing arbitrary depth nested parentheses inside link destinations
 // can create denial of service vulnerabilities if we're not careful.
@@ -72,6 +74,7 @@ pub(crate) enum ItemBody {
     Emphasis,
     Strong,
     Strikethrough,
+    ExtraEmphasis,
     Code(CowIndex),
     Link(LinkIndex),
     Image(LinkIndex),
@@ -79,11 +82,14 @@ pub(crate) enum ItemBody {
     TaskListMarker(bool), // true for checked
 
     Rule,
-    Heading(HeadingLevel), // heading level
+    Heading(HeadingLevel, Option<HeadingIndex>), // heading level
     FencedCodeBlock(CowIndex),
     IndentCodeBlock,
     Html,
     OwnedHtml(CowIndex),
+    // inline html, reported with its kind
+    InlineHtml,
+    OwnedInlineHtml(CowIndex),
     BlockQuote,
     List(bool, u8, u64), // is_tight, list character, list start index
     ListItem(usize),     // indent level
@@ -128,10 +134,103 @@ pub struct BrokenLink<'a> {
     pub reference: CowStr<'a>,
 }
 
+/// Settings for the parser that go beyond enabling or disabling features,
+/// used by [`Parser::new_with_config`]. The default configuration imposes no
+/// limits and parses according to the enabled [`Options`].
+#[derive(Clone, Debug)]
+pub struct ParserConfig {
+    /// Maximum number of bytes of input to parse. Longer input is truncated
+    /// after the last blank line within the limit, or if there is none, after
+    /// the last complete line. See [`Parser::truncated_at`].
+    pub max_input_bytes: Option<usize>,
+    /// Number of document tree nodes to allocate room for up front. Defaults
+    /// to an estimate based on the length of the input. The number of nodes
+    /// actually used is reported by `Parser::stats` with the `stats` feature.
+    pub initial_tree_capacity: Option<usize>,
+    /// Minimum number of `*`, `-` or `_` characters that make up a thematic
+    /// break. Defaults to 3. Setext heading underlines take precedence, so a
+    /// line of `---` below a paragraph still turns it into a heading, however
+    /// the minimum is set.
+    pub thematic_break_min: usize,
+    /// Maximum number of reference definitions to register, to bound the
+    /// memory used on untrusted input. Once the limit is reached, further
+    /// definitions are parsed as paragraph text.
+    pub max_reference_definitions: Option<usize>,
+    /// An ASCII punctuation character that delimits [`Tag::ExtraEmphasis`]
+    /// spans when doubled, for instance `/` for `//text//`. Like
+    /// strikethrough, the delimiters must be exactly two characters long.
+    /// Characters that already have a meaning in inline content, such as `*`,
+    /// `~`, `|` or `-`, are ignored.
+    pub extra_emphasis_delimiter: Option<char>,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        ParserConfig {
+            max_input_bytes: None,
+            initial_tree_capacity: None,
+            thematic_break_min: 3,
+            max_reference_definitions: None,
+            extra_emphasis_delimiter: None,
+        }
+    }
+}
+
+/// The block structure of a document, as found by the first of the two
+/// parsing passes. Parsers created from it with [`Parser::from_parsed_blocks`]
+/// only run the second pass, which parses the inline content of the blocks,
+/// so a document that is parsed repeatedly, for instance to render it with
+/// different broken link callbacks, only has its blocks parsed once.
+///
+/// The blocks refer to the text they were parsed from by byte offsets, so
+/// they are only valid for that exact text and the options and configuration
+/// they were parsed with, which they keep along with the text. Any edit to the
+/// text, even one within a single paragraph, means the blocks must be parsed
+/// again.
+#[derive(Clone)]
+pub struct ParsedBlocks<'input> {
+    text: &'input str,
+    options: Options,
+    truncated_at: Option<usize>,
+    tree: Tree<Item>,
+    allocs: Allocations<'input>,
+}
+
+impl<'input> ParsedBlocks<'input> {
+    /// Parses the block structure of a markdown string with given options.
+    pub fn new(text: &'input str, options: Options) -> Self {
+        ParsedBlocks::new_with_config(text, options, ParserConfig::default())
+    }
+
+    /// Parses the block structure of a markdown string with given options
+    /// and parser configuration.
+    pub fn new_with_config(text: &'input str, options: Options, config: ParserConfig) -> Self {
+        let mut truncated_at = None;
+        let text = match config.max_input_bytes {
+            Some(max_len) if text.len() > max_len => {
+                let len = block_boundary_before(text, max_len);
+                truncated_at = Some(len);
+                &text[..len]
+            }
+            _ => text,
+        };
+        let (mut tree, allocs) = run_first_pass(text, options, &config);
+        tree.reset();
+        ParsedBlocks {
+            text,
+            options,
+            truncated_at,
+            tree,
+            allocs,
+        }
+    }
+}
+
 /// Markdown event iterator.
 pub struct Parser<'input, 'callback> {
     text: &'input str,
     options: Options,
+    truncated_at: Option<usize>,
     tree: Tree<Item>,
     allocs: Allocations<'input>,
     broken_link_callback: BrokenLinkCallback<'input, 'callback>,
@@ -153,6 +252,19 @@ impl<'input, 'callback> Parser<'input, 'callback> {
         Parser::new_with_broken_link_callback(text, options, None)
     }
 
+    /// Creates a new event iterator for a markdown string with given options,
+    /// which only yields the events of the first block of the text, such as a
+    /// paragraph, a block quote or a list. This is meant for rendering a single
+    /// block in isolation, for instance in a live preview. Reference
+    /// definitions anywhere in the text are still available to links.
+    pub fn new_block(text: &'input str, options: Options) -> Self {
+        let mut parser = Parser::new_ext(text, options);
+        if let Some(first_ix) = parser.tree.cur() {
+            parser.tree[first_ix].next = None;
+        }
+        parser
+    }
+
     /// In case the parser encounters any potential links that have a broken
     /// reference (e.g `[foo]` when there is no `[foo]: ` entry at the bottom)
     /// the provided callback will be called with the reference name,
@@ -163,16 +275,57 @@ impl<'input, 'callback> Parser<'input, 'callback> {
         options: Options,
         broken_link_callback: BrokenLinkCallback<'input, 'callback>,
     ) -> Self {
-        let (mut tree, allocs) = run_first_pass(text, options);
-        tree.reset();
+        Parser::new_with_config(text, options, ParserConfig::default(), broken_link_callback)
+    }
+
+    /// Creates a new event iterator for a markdown string with given options,
+    /// parser configuration and broken link callback. See
+    /// [`new_with_broken_link_callback`](#method.new_with_broken_link_callback)
+    /// for the latter.
+    pub fn new_with_config(
+        text: &'input str,
+        options: Options,
+        config: ParserConfig,
+        broken_link_callback: BrokenLinkCallback<'input, 'callback>,
+    ) -> Self {
+        let blocks = ParsedBlocks::new_with_config(text, options, config);
+        Parser::from_blocks(blocks, broken_link_callback)
+    }
+
+    /// Creates a new event iterator from the block structure of a document,
+    /// parsed ahead of time, only parsing the inline content of the blocks.
+    /// See [`ParsedBlocks`] for when they can be reused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pulldown_cmark::{Options, ParsedBlocks, Parser};
+    ///
+    /// let blocks = ParsedBlocks::new("# Title\n\n*text*\n", Options::empty());
+    /// let first: Vec<_> = Parser::from_parsed_blocks(&blocks, None).collect();
+    /// let second: Vec<_> = Parser::from_parsed_blocks(&blocks, None).collect();
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn from_parsed_blocks(
+        blocks: &ParsedBlocks<'input>,
+        broken_link_callback: BrokenLinkCallback<'input, 'callback>,
+    ) -> Self {
+        Parser::from_blocks(blocks.clone(), broken_link_callback)
+    }
+
+    fn from_blocks(
+        blocks: ParsedBlocks<'input>,
+        broken_link_callback: BrokenLinkCallback<'input, 'callback>,
+    ) -> Self {
         let inline_stack = Default::default();
         let link_stack = Default::default();
         let html_scan_guard = Default::default();
         Parser {
-            text,
-            options,
-            tree,
-            allocs,
+            text: blocks.text,
+            options: blocks.options,
+            truncated_at: blocks.truncated_at,
+            tree: blocks.tree,
+            allocs: blocks.allocs,
             broken_link_callback,
             inline_stack,
             link_stack,
@@ -182,10 +335,91 @@ impl<'input, 'callback> Parser<'input, 'callback> {
 
     /// Returns a reference to the internal `RefDefs` object, which provides access
     /// to the internal map of reference definitions.
-    pub fn reference_definitions(&self) -> &RefDefs {
+    pub fn reference_definitions(&self) -> &RefDefs<'input> {
         &self.allocs.refdefs
     }
 
+    /// Makes the given reference definitions available to links in the
+    /// document, for instance those of a document it is included into.
+    /// Definitions in the document itself take precedence. Note that the
+    /// spans of the added definitions refer to the text they were parsed from.
+    ///
+    /// This must be called before iterating, as links are resolved on the fly.
+    ///
+    /// # Examples
+    ///
+    /// Splicing the events of an included fragment into those of the including
+    /// document, in place of a marker comment:
+    ///
+    /// ```
+    /// use pulldown_cmark::{html, Event, Parser};
+    ///
+    /// let main = "Before\n\n<!-- include -->\n\nAfter\n\n[link]: https://example.com\n";
+    /// let fragment = "Included [link].\n";
+    ///
+    /// let parser = Parser::new(main);
+    /// let defs = parser.reference_definitions().clone();
+    /// let events = parser.flat_map(|event| match event {
+    ///     Event::Html(ref html) if html.trim() == "<!-- include -->" => {
+    ///         Parser::new(fragment).with_reference_definitions(defs.clone()).collect()
+    ///     }
+    ///     event => vec![event],
+    /// });
+    ///
+    /// let mut html_buf = String::new();
+    /// html::push_html(&mut html_buf, events);
+    /// assert_eq!(html_buf, "<p>Before</p>\n<p>Included <a href=\"https://example.com\">link</a>.</p>\n<p>After</p>\n");
+    /// ```
+    pub fn with_reference_definitions(mut self, defs: RefDefs<'input>) -> Self {
+        for (label, def) in defs.0 {
+            self.allocs.refdefs.0.entry(label).or_insert(def);
+        }
+        self
+    }
+
+    /// Returns the number of bytes of input that are parsed, if the input was
+    /// truncated because it exceeded [`ParserConfig::max_input_bytes`].
+    pub fn truncated_at(&self) -> Option<usize> {
+        self.truncated_at
+    }
+
+    /// Returns all runs of `*`, `_` and `~` characters found in inline content,
+    /// as well as runs of the [`ParserConfig::extra_emphasis_delimiter`], in
+    /// source order, classified according to the flanking rules for
+    /// emphasis. Runs that can neither open nor close are included, as they
+    /// explain why text was not emphasized.
+    #[cfg(feature = "delimiter-runs")]
+    pub fn delimiter_runs(&self) -> &[DelimiterRun] {
+        &self.allocs.delimiter_runs
+    }
+
+    /// Returns statistics about the allocations made while parsing so far.
+    /// Most allocations are made up front, but inline parsing continues to
+    /// allocate as the parser is iterated over.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> ParseStats {
+        let mut stats = ParseStats {
+            links: self.allocs.links.len(),
+            alignments: self.allocs.alignments.len(),
+            tree_nodes: self.tree.len(),
+            tree_capacity: self.tree.capacity(),
+            ..Default::default()
+        };
+        let link_strs = self.allocs.links.iter().flat_map(|(_, url, title, label)| {
+            std::iter::once(url)
+                .chain(Some(title))
+                .chain(label.as_ref())
+        });
+        for cow in self.allocs.cows.iter().chain(link_strs) {
+            match cow {
+                CowStr::Borrowed(_) => stats.borrowed_strs += 1,
+                CowStr::Boxed(_) => stats.boxed_strs += 1,
+                CowStr::Inlined(_) => stats.inlined_strs += 1,
+            }
+        }
+        stats
+    }
+
     /// Handle inline markup.
     ///
     /// When the parser encounters any item indicating potential inline markup, all
@@ -214,10 +448,11 @@ impl<'input, 'callback> Parser<'input, 'callback> {
             match self.tree[cur_ix].item.body {
                 ItemBody::MaybeHtml => {
                     let next = self.tree[cur_ix].next;
-                    let autolink = if let Some(next_ix) = next {
-                        scan_autolink(block_text, self.tree[next_ix].item.start)
-                    } else {
-                        None
+                    let autolink = match next {
+                        Some(next_ix) if !self.options.contains(Options::DISABLE_AUTOLINKS) => {
+                            scan_autolink(block_text, self.tree[next_ix].item.start)
+                        }
+                        _ => None,
                     };
 
                     if let Some((ix, uri, link_type)) = autolink {
@@ -227,7 +462,7 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                             end: ix - 1,
                             body: ItemBody::Text,
                         });
-                        let link_ix = self.allocs.allocate_link(link_type, uri, "".into());
+                        let link_ix = self.allocs.allocate_link(link_type, uri, "".into(), None);
                         self.tree[cur_ix].item.body = ItemBody::Link(link_ix);
                         self.tree[cur_ix].item.end = ix;
                         self.tree[cur_ix].next = node;
@@ -247,12 +482,19 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                         });
                         if let Some((span, ix)) = inline_html {
                             let node = scan_nodes_to_ix(&self.tree, next, ix);
+                            let with_kind =
+                                self.options.contains(Options::ENABLE_INLINE_HTML_KINDS);
                             self.tree[cur_ix].item.body = if !span.is_empty() {
                                 let converted_string =
                                     String::from_utf8(span).expect("invalid utf8");
-                                ItemBody::OwnedHtml(
-                                    self.allocs.allocate_cow(converted_string.into()),
-                                )
+                                let cow_ix = self.allocs.allocate_cow(converted_string.into());
+                                if with_kind {
+                                    ItemBody::OwnedInlineHtml(cow_ix)
+                                } else {
+                                    ItemBody::OwnedHtml(cow_ix)
+                                }
+                            } else if with_kind {
+                                ItemBody::InlineHtml
                             } else {
                                 ItemBody::Html
                             };
@@ -345,13 +587,16 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                             }
                             cur = Some(tos.node);
                             cur_ix = tos.node;
-                            let link_ix = self.allocs.allocate_link(LinkType::Inline, url, title);
+                            let link_ix =
+                                self.allocs
+                                    .allocate_link(LinkType::Inline, url, title, None);
                             self.tree[cur_ix].item.body = if tos.ty == LinkStackTy::Image {
                                 ItemBody::Image(link_ix)
                             } else {
                                 ItemBody::Link(link_ix)
                             };
                             self.tree[cur_ix].child = self.tree[cur_ix].next;
+                            self.unlink_extended_autolinks(self.tree[cur_ix].child);
                             self.tree[cur_ix].next = next_node;
                             self.tree[cur_ix].item.end = next_ix;
                             if let Some(next_node_ix) = next_node {
@@ -459,7 +704,7 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                                                 let broken_link = BrokenLink {
                                                     span: (self.tree[tos.node].item.start)..end,
                                                     link_type,
-                                                    reference: link_label,
+                                                    reference: link_label.clone(),
                                                 };
 
                                                 callback(broken_link).map(|(url, title)| {
@@ -471,8 +716,12 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                                     });
 
                                 if let Some((def_link_type, url, title)) = type_url_title {
-                                    let link_ix =
-                                        self.allocs.allocate_link(def_link_type, url, title);
+                                    let link_ix = self.allocs.allocate_link(
+                                        def_link_type,
+                                        url,
+                                        title,
+                                        Some(link_label),
+                                    );
                                     self.tree[tos.node].item.body = if tos.ty == LinkStackTy::Image
                                     {
                                         ItemBody::Image(link_ix)
@@ -493,6 +742,7 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                                         if let Some(prev_ix) = prev {
                                             self.tree[prev_ix].next = None;
                                         }
+                                        self.unlink_extended_autolinks(label_node);
                                     }
 
                                     self.tree[tos.node].item.end = end;
@@ -547,6 +797,8 @@ impl<'input, 'callback> Parser<'input, 'callback> {
                             while start > el.start + el.count - match_count {
                                 let (inc, ty) = if c == b'~' {
                                     (2, ItemBody::Strikethrough)
+                                } else if c != b'*' && c != b'_' {
+                                    (2, ItemBody::ExtraEmphasis)
                                 } else if start > el.start + el.count - match_count + 1 {
                                     (2, ItemBody::Strong)
                                 } else {
@@ -637,6 +889,25 @@ impl<'input, 'callback> Parser<'input, 'callback> {
         self.inline_stack.pop_all(&mut self.tree);
     }
 
+    /// Turns the extended autolinks found in the first pass among a node and
+    /// its following siblings back into text, as links can't contain other
+    /// links. Autolinks in angle brackets are kept.
+    fn unlink_extended_autolinks(&mut self, mut cur: Option<TreeIndex>) {
+        while let Some(cur_ix) = cur {
+            let item = &mut self.tree[cur_ix].item;
+            if let ItemBody::Link(link_ix) = item.body {
+                let link_type = self.allocs[link_ix].0;
+                if matches!(link_type, LinkType::Autolink | LinkType::Email)
+                    && self.text.as_bytes()[item.start] != b'<'
+                {
+                    item.body = ItemBody::Text;
+                    self.tree[cur_ix].child = None;
+                }
+            }
+            cur = self.tree[cur_ix].next;
+        }
+    }
+
     /// Returns next byte index, url and title.
     fn scan_inline_link(
         &self,
@@ -654,9 +925,16 @@ impl<'input, 'callback> Parser<'input, 'callback> {
         let dest = unescape(dest);
         ix += dest_length;
 
-        ix += scan_while(&underlying.as_bytes()[ix..], is_ascii_whitespace);
+        let whitespace = scan_while(&underlying.as_bytes()[ix..], is_ascii_whitespace);
+        ix += whitespace;
 
-        let title = if let Some((bytes_scanned, t)) = self.scan_link_title(underlying, ix, node) {
+        // the title must be separated from the destination by whitespace
+        let title = if whitespace > 0 {
+            self.scan_link_title(underlying, ix, node)
+        } else {
+            None
+        };
+        let title = if let Some((bytes_scanned, t)) = title {
             ix += bytes_scanned;
             ix += scan_while(&underlying.as_bytes()[ix..], is_ascii_whitespace);
             t
@@ -748,12 +1026,13 @@ impl<'input, 'callback> Parser<'input, 'callback> {
         let mut span_start = self.tree[open].item.end;
         let mut span_end = self.tree[close].item.start;
         let mut buf: Option<String> = None;
+        let collapse_spaces = self.options.contains(Options::ENABLE_OLD_CODE_SPANS);
 
         // detect all-space sequences, since they are kept as-is as of commonmark 0.29
-        if !bytes[span_start..span_end].iter().all(|&b| b == b' ') {
+        if collapse_spaces || !bytes[span_start..span_end].iter().all(|&b| b == b' ') {
             let opening = matches!(bytes[span_start], b' ' | b'\r' | b'\n');
             let closing = matches!(bytes[span_end - 1], b' ' | b'\r' | b'\n');
-            let drop_enclosing_whitespace = opening && closing;
+            let drop_enclosing_whitespace = opening && closing && !collapse_spaces;
 
             if drop_enclosing_whitespace {
                 span_start += 1;
@@ -804,11 +1083,14 @@ impl<'input, 'callback> Parser<'input, 'callback> {
             }
         }
 
-        let cow = if let Some(buf) = buf {
+        let mut cow = if let Some(buf) = buf {
             buf.into()
         } else {
             self.text[span_start..span_end].into()
         };
+        if collapse_spaces {
+            cow = collapse_code_span_spaces(cow);
+        }
         if preceding_backslash {
             self.tree[open].item.body = ItemBody::Text;
             self.tree[open].item.end = self.tree[open].item.start + 1;
@@ -857,6 +1139,82 @@ impl<'input, 'callback> Parser<'input, 'callback> {
     pub fn into_offset_iter(self) -> OffsetIter<'input, 'callback> {
         OffsetIter { inner: self }
     }
+
+    /// Consumes the event iterator and produces an iterator over all links and
+    /// images of the document, in document order.
+    pub fn links(self) -> LinkIter<'input, 'callback> {
+        LinkIter {
+            inner: self.into_offset_iter(),
+        }
+    }
+
+    /// Consumes the event iterator and sends all events over a channel,
+    /// converted with [`Event::into_static`](enum.Event.html#method.into_static)
+    /// so that they can be consumed on another thread.
+    ///
+    /// The channel of a `Sender` is unbounded, so parsing never waits for the
+    /// receiving side: if it falls behind, events queue up in the channel.
+    /// Parsing stops with an error once the receiver is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::mpsc::channel;
+    /// use std::thread;
+    ///
+    /// use pulldown_cmark::{Event, Parser};
+    ///
+    /// let (sender, receiver) = channel();
+    /// let markdown = String::from("*hi*");
+    /// thread::spawn(move || Parser::new(&markdown).send_to(&sender));
+    ///
+    /// let events: Vec<Event<'static>> = receiver.iter().collect();
+    /// assert_eq!(events.len(), 5);
+    /// ```
+    pub fn send_to(self, sender: &Sender<Event<'static>>) -> Result<(), SendError<Event<'static>>> {
+        for event in self {
+            sender.send(event.into_static())?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes leading and trailing spaces from the content of a code span, and
+/// collapses interior runs of spaces, as code spans did before CommonMark 0.29.
+/// Line endings have already been replaced by spaces at this point.
+fn collapse_code_span_spaces(cow: CowStr<'_>) -> CowStr<'_> {
+    if let CowStr::Borrowed(s) = cow {
+        if !s.contains("  ") {
+            return s.trim_matches(' ').into();
+        }
+    }
+    let words: Vec<&str> = cow.split(' ').filter(|word| !word.is_empty()).collect();
+    words.join(" ").into()
+}
+
+/// Returns the length of the longest prefix of `text` of at most `max_len` bytes
+/// that ends with a blank line, or failing that, with a line ending.
+fn block_boundary_before(text: &str, max_len: usize) -> usize {
+    let bytes = &text.as_bytes()[..max_len];
+    let mut blank_line_end = 0;
+    let mut line_end = 0;
+    let mut ix = 0;
+    while ix < bytes.len() {
+        let next_ix = ix + scan_nextline(&bytes[ix..]);
+        if bytes[next_ix - 1] != b'\n' {
+            break;
+        }
+        if scan_blank_line(&bytes[ix..]).is_some() {
+            blank_line_end = next_ix;
+        }
+        line_end = next_ix;
+        ix = next_ix;
+    }
+    if blank_line_end > 0 {
+        blank_line_end
+    } else {
+        line_end
+    }
 }
 
 /// Returns number of containers scanned.
@@ -906,7 +1264,7 @@ impl<'a> Tree<Item> {
 struct InlineEl {
     start: TreeIndex, // offset of tree node
     count: usize,
-    c: u8,      // b'*' or b'_'
+    c: u8,      // b'*', b'_', b'~' or the extra emphasis delimiter
     both: bool, // can both open and close
 }
 
@@ -917,7 +1275,7 @@ struct InlineStack {
     // a strikethrough delimiter will never match with any element
     // in the stack with index smaller than
     // `lower_bounds[InlineStack::TILDES]`.
-    lower_bounds: [usize; 7],
+    lower_bounds: [usize; 8],
 }
 
 impl InlineStack {
@@ -929,6 +1287,7 @@ impl InlineStack {
     const ASTERISK_BASE: usize = 2;
     const TILDES: usize = 5;
     const UNDERSCORE_BOTH: usize = 6;
+    const EXTRA_EMPHASIS: usize = 7;
 
     fn pop_all(&mut self, tree: &mut Tree<Item>) {
         for el in self.stack.drain(..) {
@@ -936,7 +1295,7 @@ impl InlineStack {
                 tree[el.start + i].item.body = ItemBody::Text;
             }
         }
-        self.lower_bounds = [0; 7];
+        self.lower_bounds = [0; 8];
     }
 
     fn get_lowerbound(&self, c: u8, count: usize, both: bool) -> usize {
@@ -956,8 +1315,10 @@ impl InlineStack {
                     self.lower_bounds[InlineStack::ASTERISK_NOT_BOTH],
                 )
             }
-        } else {
+        } else if c == b'~' {
             self.lower_bounds[InlineStack::TILDES]
+        } else {
+            self.lower_bounds[InlineStack::EXTRA_EMPHASIS]
         }
     }
 
@@ -973,8 +1334,10 @@ impl InlineStack {
             if !both {
                 self.lower_bounds[InlineStack::ASTERISK_NOT_BOTH] = new_bound;
             }
-        } else {
+        } else if c == b'~' {
             self.lower_bounds[InlineStack::TILDES] = new_bound;
+        } else {
+            self.lower_bounds[InlineStack::EXTRA_EMPHASIS] = new_bound;
         }
     }
 
@@ -1144,8 +1507,11 @@ pub struct LinkDef<'a> {
     pub span: Range<usize>,
 }
 
-/// Tracks tree indices of code span delimiters of each length. It should prevent
-/// quadratic scanning behaviours by providing (amortized) constant time lookups.
+/// Tracks tree indices of code span delimiters of each length, as seen while
+/// scanning for a closer, so that later openers don't need to scan the same
+/// items again. Each delimiter is queued once and popped at most once, which
+/// gives amortized constant time lookups and keeps resolving `n` delimiters
+/// linear in `n`.
 struct CodeDelims {
     inner: HashMap<usize, VecDeque<TreeIndex>>,
     seen_first: bool,
@@ -1191,6 +1557,43 @@ impl CodeDelims {
     }
 }
 
+/// Allocation statistics of a parser, as returned by [`Parser::stats`].
+#[cfg(feature = "stats")]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct ParseStats {
+    /// Number of allocated strings borrowed from the source text.
+    pub borrowed_strs: usize,
+    /// Number of allocated strings owned on the heap.
+    pub boxed_strs: usize,
+    /// Number of allocated strings short enough to be stored inline.
+    pub inlined_strs: usize,
+    /// Number of links and images.
+    pub links: usize,
+    /// Number of table alignment vectors.
+    pub alignments: usize,
+    /// Number of nodes in the document tree.
+    pub tree_nodes: usize,
+    /// Number of nodes the document tree has room for without reallocating.
+    pub tree_capacity: usize,
+}
+
+/// A run of emphasis or strikethrough delimiter characters, with its
+/// classification according to the flanking rules. Returned by
+/// [`Parser::delimiter_runs`].
+#[cfg(feature = "delimiter-runs")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DelimiterRun {
+    /// Range of the run in the source string. Its length is the run length.
+    pub range: Range<usize>,
+    /// The delimiter character: `*`, `_`, `~` or the
+    /// [`ParserConfig::extra_emphasis_delimiter`].
+    pub delimiter: char,
+    /// Whether the run can open emphasis.
+    pub can_open: bool,
+    /// Whether the run can close emphasis.
+    pub can_close: bool,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub(crate) struct LinkIndex(usize);
 
@@ -1200,12 +1603,25 @@ pub(crate) struct CowIndex(usize);
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub(crate) struct AlignmentIndex(usize);
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) struct HeadingIndex(NonZeroUsize);
+
+/// The id and classes of a heading attribute block, such as `{#id .class}`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct HeadingAttributes<'a> {
+    pub id: Option<&'a str>,
+    pub classes: Vec<&'a str>,
+}
+
 #[derive(Clone)]
 pub(crate) struct Allocations<'a> {
     pub refdefs: RefDefs<'a>,
-    links: Vec<(LinkType, CowStr<'a>, CowStr<'a>)>,
+    links: Vec<(LinkType, CowStr<'a>, CowStr<'a>, Option<CowStr<'a>>)>,
     cows: Vec<CowStr<'a>>,
     alignments: Vec<Vec<Alignment>>,
+    headings: Vec<HeadingAttributes<'a>>,
+    #[cfg(feature = "delimiter-runs")]
+    pub delimiter_runs: Vec<DelimiterRun>,
 }
 
 /// Keeps track of the reference definitions defined in the document.
@@ -1216,9 +1632,13 @@ impl<'input, 'b, 's> RefDefs<'input>
 where
     's: 'b,
 {
-    /// Performs a lookup on reference label using unicode case folding.
+    /// Performs a lookup on reference label using unicode case folding, or
+    /// an exact match for definitions parsed with
+    /// [`Options::ENABLE_CASE_SENSITIVE_REFERENCES`](struct.Options.html#associatedconstant.ENABLE_CASE_SENSITIVE_REFERENCES).
     pub fn get(&'s self, key: &'b str) -> Option<&'b LinkDef<'input>> {
-        self.0.get(&UniCase::new(key.into()))
+        self.0
+            .get(&LinkLabel::new(key.into(), false))
+            .or_else(|| self.0.get(&LinkLabel::new(key.into(), true)))
     }
 
     /// Provides an iterator over all the document's reference definitions.
@@ -1234,6 +1654,9 @@ impl<'a> Allocations<'a> {
             links: Vec::with_capacity(128),
             cows: Vec::new(),
             alignments: Vec::new(),
+            headings: Vec::new(),
+            #[cfg(feature = "delimiter-runs")]
+            delimiter_runs: Vec::new(),
         }
     }
 
@@ -1243,9 +1666,15 @@ impl<'a> Allocations<'a> {
         CowIndex(ix)
     }
 
-    pub fn allocate_link(&mut self, ty: LinkType, url: CowStr<'a>, title: CowStr<'a>) -> LinkIndex {
+    pub fn allocate_link(
+        &mut self,
+        ty: LinkType,
+        url: CowStr<'a>,
+        title: CowStr<'a>,
+        label: Option<CowStr<'a>>,
+    ) -> LinkIndex {
         let ix = self.links.len();
-        self.links.push((ty, url, title));
+        self.links.push((ty, url, title, label));
         LinkIndex(ix)
     }
 
@@ -1254,6 +1683,11 @@ impl<'a> Allocations<'a> {
         self.alignments.push(alignment);
         AlignmentIndex(ix)
     }
+
+    pub fn allocate_heading(&mut self, attrs: HeadingAttributes<'a>) -> HeadingIndex {
+        self.headings.push(attrs);
+        HeadingIndex(NonZeroUsize::new(self.headings.len()).unwrap())
+    }
 }
 
 impl<'a> Index<CowIndex> for Allocations<'a> {
@@ -1265,7 +1699,7 @@ impl<'a> Index<CowIndex> for Allocations<'a> {
 }
 
 impl<'a> Index<LinkIndex> for Allocations<'a> {
-    type Output = (LinkType, CowStr<'a>, CowStr<'a>);
+    type Output = (LinkType, CowStr<'a>, CowStr<'a>, Option<CowStr<'a>>);
 
     fn index(&self, ix: LinkIndex) -> &Self::Output {
         self.links.index(ix.0)
@@ -1280,6 +1714,14 @@ impl<'a> Index<AlignmentIndex> for Allocations<'a> {
     }
 }
 
+impl<'a> Index<HeadingIndex> for Allocations<'a> {
+    type Output = HeadingAttributes<'a>;
+
+    fn index(&self, ix: HeadingIndex) -> &Self::Output {
+        self.headings.index(ix.0.get() - 1)
+    }
+}
+
 /// A struct containing information on the reachability of certain inline HTML
 /// elements. In particular, for cdata elements (`<![CDATA[`), processing
 /// elements (`<?`) and declarations (`<!DECLARATION`). The respectives usizes
@@ -1308,9 +1750,20 @@ pub struct OffsetIter<'a, 'b> {
 
 impl<'a, 'b> OffsetIter<'a, 'b> {
     /// Returns a reference to the internal reference definition tracker.
-    pub fn reference_definitions(&self) -> &RefDefs {
+    pub fn reference_definitions(&self) -> &RefDefs<'a> {
         self.inner.reference_definitions()
     }
+
+    /// Consumes the offset iterator and produces an iterator which additionally
+    /// yields a [`BlockItem::Boundary`] with the source range of each top-level
+    /// block, right before the events of that block.
+    pub fn with_block_boundaries(self) -> BlockBoundaryIter<'a, 'b> {
+        BlockBoundaryIter {
+            inner: self,
+            pending: VecDeque::new(),
+            next_block: None,
+        }
+    }
 }
 
 impl<'a, 'b> Iterator for OffsetIter<'a, 'b> {
@@ -1346,74 +1799,201 @@ impl<'a, 'b> Iterator for OffsetIter<'a, 'b> {
     }
 }
 
-fn item_to_tag<'a>(item: &Item, allocs: &Allocations<'a>) -> Tag<'a> {
-    match item.body {
-        ItemBody::Paragraph => Tag::Paragraph,
-        ItemBody::Emphasis => Tag::Emphasis,
-        ItemBody::Strong => Tag::Strong,
-        ItemBody::Strikethrough => Tag::Strikethrough,
-        ItemBody::Link(link_ix) => {
-            let &(ref link_type, ref url, ref title) = allocs.index(link_ix);
-            Tag::Link(*link_type, url.clone(), title.clone())
-        }
-        ItemBody::Image(link_ix) => {
-            let &(ref link_type, ref url, ref title) = allocs.index(link_ix);
-            Tag::Image(*link_type, url.clone(), title.clone())
-        }
-        ItemBody::Heading(level) => Tag::Heading(level),
-        ItemBody::FencedCodeBlock(cow_ix) => {
-            Tag::CodeBlock(CodeBlockKind::Fenced(allocs[cow_ix].clone()))
+/// Returns the range of the raw content of a code span, given the source text
+/// and the range reported for its `Code` event by an [`OffsetIter`]. The raw
+/// content is everything between the backtick delimiters, without the
+/// stripping of enclosing spaces and conversion of line endings to spaces that
+/// is applied to the event's text.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{code_span_raw_range, Event, Parser};
+///
+/// let text = "`` `foo` ``";
+/// let (event, range) = Parser::new(text).into_offset_iter().nth(1).unwrap();
+///
+/// assert_eq!(event, Event::Code("`foo`".into()));
+/// assert_eq!(&text[code_span_raw_range(text, range)], " `foo` ");
+/// ```
+pub fn code_span_raw_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let bytes = &text.as_bytes()[range.clone()];
+    let delim_len = bytes.iter().take_while(|&&b| b == b'`').count();
+    (range.start + delim_len)..(range.end - delim_len)
+}
+
+/// Item yielded by a [`BlockBoundaryIter`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockItem<'a> {
+    /// Start of a top-level block, with the range it spans in the source string.
+    /// The events of the block follow.
+    Boundary(Range<usize>),
+    /// An event and its range in the source string, as yielded by an [`OffsetIter`].
+    Event(Event<'a>, Range<usize>),
+}
+
+/// Markdown event and source range iterator which marks the boundaries of
+/// top-level blocks, so that they can be processed or cached individually.
+///
+/// HTML blocks have no enclosing tag, so consecutive lines of HTML are
+/// reported as one block. HTML blocks that directly follow each other, with
+/// no blank line in between, are reported together.
+///
+/// Constructed from an `OffsetIter` using its
+/// [`with_block_boundaries`](struct.OffsetIter.html#method.with_block_boundaries) method.
+pub struct BlockBoundaryIter<'a, 'b> {
+    inner: OffsetIter<'a, 'b>,
+    /// Events of the current block read ahead while looking for the end of
+    /// an HTML block.
+    pending: VecDeque<(Event<'a>, Range<usize>)>,
+    /// First event of the next top-level block, if it has been read ahead.
+    next_block: Option<(Event<'a>, Range<usize>)>,
+}
+
+impl<'a, 'b> Iterator for BlockBoundaryIter<'a, 'b> {
+    type Item = BlockItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((event, range)) = self.pending.pop_front() {
+            return Some(BlockItem::Event(event, range));
         }
-        ItemBody::IndentCodeBlock => Tag::CodeBlock(CodeBlockKind::Indented),
-        ItemBody::BlockQuote => Tag::BlockQuote,
-        ItemBody::List(_, c, listitem_start) => {
-            if c == b'.' || c == b')' {
-                Tag::List(Some(listitem_start))
-            } else {
-                Tag::List(None)
+        let (event, range) = match self.next_block.take() {
+            Some(item) => item,
+            None => {
+                let top_level = self.inner.inner.tree.spine_len() == 0;
+                let (event, range) = self.inner.next()?;
+                if !top_level {
+                    return Some(BlockItem::Event(event, range));
+                }
+                (event, range)
+            }
+        };
+        let mut block = range.clone();
+        let is_html = matches!(event, Event::Html(_));
+        self.pending.push_back((event, range));
+        if is_html {
+            // Lines of HTML follow each other at the top level. The only
+            // source between two lines of the same block is the carriage
+            // return of a CRLF line ending.
+            while self.inner.inner.tree.spine_len() == 0 {
+                let (event, range) = match self.inner.next() {
+                    Some(item) => item,
+                    None => break,
+                };
+                if matches!(event, Event::Html(_))
+                    && !self.inner.inner.text[block.end..range.start].contains('\n')
+                {
+                    block.end = range.end;
+                    self.pending.push_back((event, range));
+                } else {
+                    self.next_block = Some((event, range));
+                    break;
+                }
             }
         }
-        ItemBody::ListItem(_) => Tag::Item,
-        ItemBody::TableHead => Tag::TableHead,
-        ItemBody::TableCell => Tag::TableCell,
-        ItemBody::TableRow => Tag::TableRow,
-        ItemBody::Table(alignment_ix) => Tag::Table(allocs[alignment_ix].clone()),
-        ItemBody::FootnoteDefinition(cow_ix) => Tag::FootnoteDefinition(allocs[cow_ix].clone()),
-        _ => panic!("unexpected item body {:?}", item.body),
+        Some(BlockItem::Boundary(block))
     }
 }
 
-fn item_to_event<'a>(item: Item, text: &'a str, allocs: &Allocations<'a>) -> Event<'a> {
-    let tag = match item.body {
-        ItemBody::Text => return Event::Text(text[item.start..item.end].into()),
-        ItemBody::Code(cow_ix) => return Event::Code(allocs[cow_ix].clone()),
-        ItemBody::SynthesizeText(cow_ix) => return Event::Text(allocs[cow_ix].clone()),
-        ItemBody::SynthesizeChar(c) => return Event::Text(c.into()),
-        ItemBody::Html => return Event::Html(text[item.start..item.end].into()),
-        ItemBody::OwnedHtml(cow_ix) => return Event::Html(allocs[cow_ix].clone()),
-        ItemBody::SoftBreak => return Event::SoftBreak,
-        ItemBody::HardBreak => return Event::HardBreak,
-        ItemBody::FootnoteReference(cow_ix) => {
-            return Event::FootnoteReference(allocs[cow_ix].clone())
+/// A link or image, as yielded by [`LinkIter`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkInfo<'a> {
+    /// How the link or image was written, such as inline or as a reference.
+    pub link_type: LinkType,
+    /// Whether this is an image rather than a link.
+    pub is_image: bool,
+    /// The destination URL.
+    pub dest: CowStr<'a>,
+    /// The title, which is empty if there is none.
+    pub title: CowStr<'a>,
+    /// Range of the whole link or image in the source.
+    pub range: Range<usize>,
+}
+
+/// Iterator over the links and images of a document.
+///
+/// Constructed from a `Parser` using its [`links`](struct.Parser.html#method.links)
+/// method.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{LinkType, Parser};
+///
+/// let links: Vec<_> = Parser::new("[a](b) ![c][d]\n\n[d]: e").links().collect();
+///
+/// assert_eq!(links[0].link_type, LinkType::Inline);
+/// assert_eq!(&*links[0].dest, "b");
+/// assert!(links[1].is_image);
+/// assert_eq!(&*links[1].dest, "e");
+/// assert_eq!(links[1].range, 7..14);
+/// ```
+pub struct LinkIter<'a, 'b> {
+    inner: OffsetIter<'a, 'b>,
+}
+
+impl<'a, 'b> Iterator for LinkIter<'a, 'b> {
+    type Item = LinkInfo<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (event, range) in &mut self.inner {
+            let (link_type, is_image, dest, title) = match event {
+                Event::Start(Tag::Link(link_type, dest, title, _)) => {
+                    (link_type, false, dest, title)
+                }
+                Event::Start(Tag::Image(link_type, dest, title)) => (link_type, true, dest, title),
+                _ => continue,
+            };
+            return Some(LinkInfo {
+                link_type,
+                is_image,
+                dest,
+                title,
+                range,
+            });
         }
-        ItemBody::TaskListMarker(checked) => return Event::TaskListMarker(checked),
-        ItemBody::Rule => return Event::Rule,
+        None
+    }
+}
 
+fn item_to_tag<'a>(item: &Item, allocs: &Allocations<'a>) -> Tag<'a> {
+    match item.body {
         ItemBody::Paragraph => Tag::Paragraph,
         ItemBody::Emphasis => Tag::Emphasis,
         ItemBody::Strong => Tag::Strong,
         ItemBody::Strikethrough => Tag::Strikethrough,
+        ItemBody::ExtraEmphasis => Tag::ExtraEmphasis,
         ItemBody::Link(link_ix) => {
-            let &(ref link_type, ref url, ref title) = allocs.index(link_ix);
-            Tag::Link(*link_type, url.clone(), title.clone())
+            let &(ref link_type, ref url, ref title, ref label) = allocs.index(link_ix);
+            Tag::Link(
+                *link_type,
+                replace_nul(url.clone()),
+                replace_nul(title.clone()),
+                label.clone().map(replace_nul),
+            )
         }
         ItemBody::Image(link_ix) => {
-            let &(ref link_type, ref url, ref title) = allocs.index(link_ix);
-            Tag::Image(*link_type, url.clone(), title.clone())
+            let &(ref link_type, ref url, ref title, _) = allocs.index(link_ix);
+            Tag::Image(
+                *link_type,
+                replace_nul(url.clone()),
+                replace_nul(title.clone()),
+            )
+        }
+        ItemBody::Heading(level, Some(heading_ix)) => {
+            let HeadingAttributes { id, classes } = &allocs[heading_ix];
+            Tag::Heading(
+                level,
+                id.map(|id| replace_nul(id.into())),
+                classes
+                    .iter()
+                    .map(|&class| replace_nul(class.into()))
+                    .collect(),
+            )
         }
-        ItemBody::Heading(level) => Tag::Heading(level),
+        ItemBody::Heading(level, None) => Tag::Heading(level, None, Vec::new()),
         ItemBody::FencedCodeBlock(cow_ix) => {
-            Tag::CodeBlock(CodeBlockKind::Fenced(allocs[cow_ix].clone()))
+            Tag::CodeBlock(CodeBlockKind::Fenced(replace_nul(allocs[cow_ix].clone())))
         }
         ItemBody::IndentCodeBlock => Tag::CodeBlock(CodeBlockKind::Indented),
         ItemBody::BlockQuote => Tag::BlockQuote,
@@ -1429,11 +2009,67 @@ fn item_to_event<'a>(item: Item, text: &'a str, allocs: &Allocations<'a>) -> Eve
         ItemBody::TableCell => Tag::TableCell,
         ItemBody::TableRow => Tag::TableRow,
         ItemBody::Table(alignment_ix) => Tag::Table(allocs[alignment_ix].clone()),
-        ItemBody::FootnoteDefinition(cow_ix) => Tag::FootnoteDefinition(allocs[cow_ix].clone()),
+        ItemBody::FootnoteDefinition(cow_ix) => {
+            Tag::FootnoteDefinition(replace_nul(allocs[cow_ix].clone()))
+        }
         _ => panic!("unexpected item body {:?}", item.body),
-    };
+    }
+}
+
+/// Classifies a piece of inline HTML, which must have been recognized by
+/// `scan_inline_html`.
+fn inline_html_kind(html: &str) -> InlineHtmlKind {
+    if html.starts_with("<!--") {
+        InlineHtmlKind::Comment
+    } else if html.starts_with("<![CDATA[") {
+        InlineHtmlKind::CData
+    } else if html.starts_with("<!") {
+        InlineHtmlKind::Declaration
+    } else if html.starts_with("<?") {
+        InlineHtmlKind::ProcessingInstruction
+    } else if html.starts_with("</") {
+        InlineHtmlKind::CloseTag
+    } else if html.ends_with("/>") {
+        InlineHtmlKind::SelfClosingTag
+    } else {
+        InlineHtmlKind::OpenTag
+    }
+}
 
-    Event::Start(tag)
+/// Replaces NUL characters with U+FFFD, as the spec requires for security.
+fn replace_nul(s: CowStr) -> CowStr {
+    if memchr::memchr(0, s.as_bytes()).is_some() {
+        s.replace('\0', "\u{FFFD}").into()
+    } else {
+        s
+    }
+}
+
+fn item_to_event<'a>(item: Item, text: &'a str, allocs: &Allocations<'a>) -> Event<'a> {
+    match item.body {
+        ItemBody::Text => Event::Text(replace_nul(text[item.start..item.end].into())),
+        ItemBody::Code(cow_ix) => Event::Code(replace_nul(allocs[cow_ix].clone())),
+        ItemBody::SynthesizeText(cow_ix) => Event::Text(replace_nul(allocs[cow_ix].clone())),
+        ItemBody::SynthesizeChar(c) => Event::Text(c.into()),
+        ItemBody::Html => Event::Html(replace_nul(text[item.start..item.end].into())),
+        ItemBody::OwnedHtml(cow_ix) => Event::Html(replace_nul(allocs[cow_ix].clone())),
+        ItemBody::InlineHtml => {
+            let html = replace_nul(text[item.start..item.end].into());
+            Event::InlineHtml(inline_html_kind(&html), html)
+        }
+        ItemBody::OwnedInlineHtml(cow_ix) => {
+            let html = replace_nul(allocs[cow_ix].clone());
+            Event::InlineHtml(inline_html_kind(&html), html)
+        }
+        ItemBody::SoftBreak => Event::SoftBreak,
+        ItemBody::HardBreak => Event::HardBreak,
+        ItemBody::FootnoteReference(cow_ix) => {
+            Event::FootnoteReference(replace_nul(allocs[cow_ix].clone()))
+        }
+        ItemBody::TaskListMarker(checked) => Event::TaskListMarker(checked),
+        ItemBody::Rule => Event::Rule,
+        _ => Event::Start(item_to_tag(&item, allocs)),
+    }
 }
 
 impl<'a, 'b> Iterator for Parser<'a, 'b> {
@@ -1467,6 +2103,7 @@ impl<'a, 'b> Iterator for Parser<'a, 'b> {
 }
 
 impl FusedIterator for Parser<'_, '_> {}
+impl FusedIterator for LinkIter<'_, '_> {}
 
 #[cfg(test)]
 mod test {
@@ -1475,6 +2112,16 @@ mod test {
 
     // TODO: move these tests to tests/html.rs?
 
+    fn raw_code_spans(text: &str) -> Vec<(CowStr<'_>, &str)> {
+        Parser::new(text)
+            .into_offset_iter()
+            .filter_map(|(event, range)| match event {
+                Event::Code(code) => Some((code, &text[code_span_raw_range(text, range)])),
+                _ => None,
+            })
+            .collect()
+    }
+
     fn parser_with_extensions(text: &str) -> Parser<'_, 'static> {
         let mut opts = Options::empty();
         opts.insert(Options::ENABLE_TABLES);
@@ -1669,6 +2316,19 @@ mod test {
         assert_eq!(expected_offsets, event_offsets);
     }
 
+    #[test]
+    fn footnote_reference_offset() {
+        let markdown = "a[^note] b\n\n> c [^x]\n\n[^note]: n\n";
+        let ranges: Vec<_> = parser_with_extensions(markdown)
+            .into_offset_iter()
+            .filter_map(|(ev, range)| match ev {
+                Event::FootnoteReference(..) => Some(&markdown[range]),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["[^note]", "[^x]"], ranges);
+    }
+
     // FIXME: add this one regression suite
     #[test]
     fn link_def_at_eof() {
@@ -1754,7 +2414,7 @@ mod test {
         let mut link_tag_count = 0;
         for (typ, url, title) in parser.filter_map(|event| match event {
             Event::Start(tag) | Event::End(tag) => match tag {
-                Tag::Link(typ, url, title) => Some((typ, url, title)),
+                Tag::Link(typ, url, title, _) => Some((typ, url, title)),
                 _ => None,
             },
             _ => None,
@@ -1858,4 +2518,705 @@ text
             Some(&mut function),
         ) {}
     }
+
+    #[test]
+    fn code_span_raw_content() {
+        assert_eq!(
+            vec![(CowStr::from("`foo`"), " `foo` ")],
+            raw_code_spans("`` `foo` ``")
+        );
+    }
+
+    #[test]
+    fn code_span_raw_content_line_ending() {
+        assert_eq!(
+            vec![(CowStr::from("foo bar"), "foo\nbar")],
+            raw_code_spans("`foo\nbar`")
+        );
+    }
+
+    #[test]
+    fn code_span_raw_content_after_backslash() {
+        assert_eq!(
+            vec![(CowStr::from("foo"), "foo")],
+            raw_code_spans("\\``foo`")
+        );
+    }
+
+    fn link_labels(text: &str) -> Vec<(LinkType, Option<CowStr<'_>>)> {
+        Parser::new(text)
+            .filter_map(|event| match event {
+                Event::Start(Tag::Link(link_type, _, _, label)) => Some((link_type, label)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn link_label_reference() {
+        assert_eq!(
+            vec![(LinkType::Reference, Some("Foo Bar".into()))],
+            link_labels("[text][Foo Bar]\n\n[foo bar]: /url\n")
+        );
+    }
+
+    #[test]
+    fn link_label_collapsed() {
+        assert_eq!(
+            vec![(LinkType::Collapsed, Some("Foo".into()))],
+            link_labels("[Foo][]\n\n[foo]: /url\n")
+        );
+    }
+
+    #[test]
+    fn link_label_shortcut() {
+        assert_eq!(
+            vec![(LinkType::Shortcut, Some("foo".into()))],
+            link_labels("[foo]\n\n[foo]: /url\n")
+        );
+    }
+
+    #[test]
+    fn link_label_normalized() {
+        assert_eq!(
+            vec![(LinkType::Reference, Some("Foo Bar".into()))],
+            link_labels("[text][ Foo \t\n  Bar ]\n\n[foo bar]: /url\n")
+        );
+    }
+
+    #[test]
+    fn link_label_inline_and_autolink() {
+        assert_eq!(
+            vec![(LinkType::Inline, None), (LinkType::Autolink, None)],
+            link_labels("[foo](/url) <http://a.b>\n")
+        );
+    }
+
+    #[test]
+    fn top_level_block_ranges() {
+        let text = "# title\n\npara\ngraph\n\n- a\n\n  > b\n- c\n\n***\n> quote\n";
+        let blocks: Vec<_> = Parser::new(text)
+            .into_offset_iter()
+            .with_block_boundaries()
+            .filter_map(|item| match item {
+                BlockItem::Boundary(range) => Some(&text[range]),
+                BlockItem::Event(..) => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "# title\n",
+                "para\ngraph\n",
+                "- a\n\n  > b\n- c\n\n",
+                "***\n",
+                "> quote\n"
+            ],
+            blocks
+        );
+    }
+
+    #[test]
+    fn html_block_is_one_block() {
+        let text = "<div>\n  a\n</div>\n\n<pre>\r\n\r\nb\r\n</pre>\r\npara\n<!-- c -->\n";
+        let blocks: Vec<_> = Parser::new(text)
+            .into_offset_iter()
+            .with_block_boundaries()
+            .filter_map(|item| match item {
+                BlockItem::Boundary(range) => Some(&text[range]),
+                BlockItem::Event(..) => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "<div>\n  a\n</div>\n",
+                "<pre>\r\n\r\nb\r\n</pre>\r\n",
+                "para\n",
+                "<!-- c -->\n",
+            ],
+            blocks
+        );
+    }
+
+    #[test]
+    fn block_boundaries_precede_events() {
+        let items: Vec<_> = Parser::new("a\n\nb\n")
+            .into_offset_iter()
+            .with_block_boundaries()
+            .collect();
+        assert_eq!(
+            vec![
+                BlockItem::Boundary(0..2),
+                BlockItem::Event(Event::Start(Tag::Paragraph), 0..2),
+                BlockItem::Event(Event::Text("a".into()), 0..1),
+                BlockItem::Event(Event::End(Tag::Paragraph), 0..2),
+                BlockItem::Boundary(3..5),
+                BlockItem::Event(Event::Start(Tag::Paragraph), 3..5),
+                BlockItem::Event(Event::Text("b".into()), 3..4),
+                BlockItem::Event(Event::End(Tag::Paragraph), 3..5),
+            ],
+            items
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn parse_stats() {
+        let mut parser = Parser::new_ext(
+            "[a](b \"t\") [c](d\\*e)\n\n|x|\n|-|\n",
+            Options::ENABLE_TABLES,
+        );
+        assert_eq!(0, parser.stats().links);
+        parser.by_ref().for_each(drop);
+        let stats = parser.stats();
+        assert_eq!(2, stats.links);
+        assert_eq!(1, stats.alignments);
+        assert_eq!(2, stats.boxed_strs);
+        assert_eq!(2, stats.borrowed_strs);
+        assert!(stats.tree_nodes > 0);
+        assert!(stats.tree_capacity >= stats.tree_nodes);
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn initial_tree_capacity() {
+        let text = "# heading\n\n- *a*\n- `b`\n\n> [c](d)\n".repeat(20);
+        let config = ParserConfig {
+            initial_tree_capacity: Some(1000),
+            ..Default::default()
+        };
+        let mut parser = Parser::new_with_config(&text, Options::empty(), config, None);
+        let capacity = parser.stats().tree_capacity;
+        assert!(capacity >= 1000);
+        parser.by_ref().for_each(drop);
+        let stats = parser.stats();
+        assert!(stats.tree_nodes > 128);
+        assert_eq!(capacity, stats.tree_capacity);
+    }
+
+    fn parser_with_max_input_bytes(text: &str, max_input_bytes: usize) -> Parser<'_, '_> {
+        let config = ParserConfig {
+            max_input_bytes: Some(max_input_bytes),
+            ..Default::default()
+        };
+        Parser::new_with_config(text, Options::empty(), config, None)
+    }
+
+    #[test]
+    fn max_input_bytes_truncates_at_blank_line() {
+        let text = "a paragraph\nof two lines\n\n".repeat(10_000);
+        let mut parser = parser_with_max_input_bytes(&text, 100);
+        assert_eq!(Some(78), parser.truncated_at());
+        let paragraphs = parser
+            .by_ref()
+            .filter(|event| *event == Event::Start(Tag::Paragraph))
+            .count();
+        assert_eq!(3, paragraphs);
+    }
+
+    #[test]
+    fn max_input_bytes_truncates_at_line_end() {
+        let parser = parser_with_max_input_bytes("first line\nsecond line\nthird line\n", 30);
+        assert_eq!(Some(23), parser.truncated_at());
+        let parser = parser_with_max_input_bytes("a single line", 5);
+        assert_eq!(Some(0), parser.truncated_at());
+        assert_eq!(0, parser.count());
+    }
+
+    #[test]
+    fn max_input_bytes_not_exceeded() {
+        let parser = parser_with_max_input_bytes("short\n", 6);
+        assert_eq!(None, parser.truncated_at());
+        assert_eq!(3, parser.count());
+    }
+
+    #[test]
+    #[cfg(feature = "delimiter-runs")]
+    fn delimiter_run_classification() {
+        let text = "**a** b_c_ * d ~~e~~ ==f";
+        let config = ParserConfig {
+            extra_emphasis_delimiter: Some('='),
+            ..Default::default()
+        };
+        let parser = Parser::new_with_config(text, Options::ENABLE_STRIKETHROUGH, config, None);
+        let runs: Vec<_> = parser
+            .delimiter_runs()
+            .iter()
+            .map(|run| (&text[run.range.clone()], run.can_open, run.can_close))
+            .collect();
+        assert_eq!(
+            vec![
+                ("**", true, false),
+                ("**", false, true),
+                ("_", false, false),
+                ("_", false, true),
+                ("*", false, false),
+                ("~~", true, false),
+                ("~~", false, true),
+                ("==", true, false),
+            ],
+            runs
+        );
+    }
+
+    #[test]
+    fn links_in_document_order() {
+        let text = "[a](/u \"t\") <http://x.y> *[b][r] <me@x.y>*\n\n\
+                    > ![c](/i.png)\n\n[r]: /ref\n";
+        let links: Vec<_> = Parser::new(text)
+            .links()
+            .map(|link| {
+                (
+                    link.link_type,
+                    link.is_image,
+                    link.dest,
+                    link.title,
+                    link.range,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (LinkType::Inline, false, "/u".into(), "t".into(), 0..11),
+                (
+                    LinkType::Autolink,
+                    false,
+                    "http://x.y".into(),
+                    "".into(),
+                    12..24
+                ),
+                (LinkType::Reference, false, "/ref".into(), "".into(), 26..32),
+                (LinkType::Email, false, "me@x.y".into(), "".into(), 33..41),
+                (LinkType::Inline, true, "/i.png".into(), "".into(), 46..58),
+            ],
+            links
+        );
+    }
+
+    #[test]
+    fn single_block() {
+        let events: Vec<_> = Parser::new_block("> a\n> b\n\nc\n", Options::empty()).collect();
+        assert_eq!(
+            vec![
+                Event::Start(Tag::BlockQuote),
+                Event::Start(Tag::Paragraph),
+                Event::Text("a".into()),
+                Event::SoftBreak,
+                Event::Text("b".into()),
+                Event::End(Tag::Paragraph),
+                Event::End(Tag::BlockQuote),
+            ],
+            events
+        );
+
+        let text = "```rust\nfn main() {}\n```\n# heading\n";
+        let mut s = String::new();
+        crate::html::push_html(&mut s, Parser::new_block(text, Options::empty()));
+        assert_eq!(
+            "<pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n",
+            s
+        );
+
+        assert_eq!(0, Parser::new_block("", Options::empty()).count());
+        assert_eq!(0, Parser::new_block("[a]: b\n", Options::empty()).count());
+    }
+
+    #[test]
+    fn thematic_break_min() {
+        let config = ParserConfig {
+            thematic_break_min: 4,
+            ..Default::default()
+        };
+        let text = "---\n\n* * *\n\n____\n\n- - - -\n\na\n---\n";
+        let mut s = String::new();
+        crate::html::push_html(
+            &mut s,
+            Parser::new_with_config(text, Options::empty(), config, None),
+        );
+        assert_eq!(
+            "<p>---</p>\n\
+             <ul>\n<li>\n<ul>\n<li>\n<ul>\n<li></li>\n</ul>\n</li>\n</ul>\n</li>\n</ul>\n\
+             <hr />\n<hr />\n<h2>a</h2>\n",
+            s
+        );
+    }
+
+    #[test]
+    fn inline_html_kinds() {
+        let text = "<b>a</b><br/><!-- c --><?p?><!X y><![CDATA[z]]>\n\n<div>\n";
+        let html: Vec<_> = Parser::new_ext(text, Options::ENABLE_INLINE_HTML_KINDS)
+            .filter(|event| matches!(event, Event::Html(..) | Event::InlineHtml(..)))
+            .collect();
+        assert_eq!(
+            vec![
+                Event::InlineHtml(InlineHtmlKind::OpenTag, "<b>".into()),
+                Event::InlineHtml(InlineHtmlKind::CloseTag, "</b>".into()),
+                Event::InlineHtml(InlineHtmlKind::SelfClosingTag, "<br/>".into()),
+                Event::InlineHtml(InlineHtmlKind::Comment, "<!-- c -->".into()),
+                Event::InlineHtml(InlineHtmlKind::ProcessingInstruction, "<?p?>".into()),
+                Event::InlineHtml(InlineHtmlKind::Declaration, "<!X y>".into()),
+                Event::InlineHtml(InlineHtmlKind::CData, "<![CDATA[z]]>".into()),
+                // html blocks are not affected
+                Event::Html("<div>\n".into()),
+            ],
+            html
+        );
+
+        // without the option, inline html is reported as plain html events
+        assert!(Parser::new("<b>a</b>").all(|event| !matches!(event, Event::InlineHtml(..))));
+    }
+
+    #[test]
+    fn omit_blank_paragraphs() {
+        let render = |text| {
+            let mut s = String::new();
+            crate::html::push_html(
+                &mut s,
+                Parser::new_ext(text, Options::OMIT_BLANK_PARAGRAPHS),
+            );
+            s
+        };
+        assert_eq!(
+            "<p>a</p>\n<p>b</p>\n",
+            render("a\n\n\u{a0}\u{2003}\n\u{a0}\n\nb\n")
+        );
+        assert_eq!("", render("\u{a0}\n"));
+        assert_eq!(
+            "<ul>\n<li></li>\n<li>b</li>\n</ul>\n<blockquote>\n</blockquote>\n",
+            render("- \u{a0}\n- b\n\n> \u{a0}\n")
+        );
+
+        assert_eq!("<p>\u{a0}</p>\n", render("&nbsp;\n"));
+        assert_eq!("<p><img src=\"x\" alt=\"\" /></p>\n", render("![](x)\n"));
+        assert_eq!("<h1>\u{a0}</h1>\n", render("\u{a0}\n===\n"));
+    }
+
+    #[test]
+    fn disable_empty_atx_headings() {
+        let render = |text, options| {
+            let mut s = String::new();
+            crate::html::push_html(&mut s, Parser::new_ext(text, options));
+            s
+        };
+        let text = "#\n\n## ##\n\na\n###\n# b\n";
+        assert_eq!(
+            "<h1></h1>\n<h2></h2>\n<p>a</p>\n<h3></h3>\n<h1>b</h1>\n",
+            render(text, Options::empty())
+        );
+        assert_eq!(
+            "<p>#</p>\n<p>## ##</p>\n<p>a\n###</p>\n<h1>b</h1>\n",
+            render(text, Options::DISABLE_EMPTY_ATX_HEADINGS)
+        );
+    }
+
+    #[test]
+    fn send_events_to_other_thread() {
+        let text = "# a\n\n> *b* `c` [d](e \"f\")\n\n```g\nh\n```\n";
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let markdown = text.to_string();
+        let handle =
+            std::thread::spawn(move || Parser::new_ext(&markdown, Options::all()).send_to(&sender));
+
+        let events: Vec<Event<'static>> = receiver.iter().collect();
+        assert!(handle.join().unwrap().is_ok());
+        let expected: Vec<_> = Parser::new_ext(text, Options::all()).collect();
+        assert_eq!(expected, events);
+    }
+
+    #[test]
+    fn send_events_to_dropped_receiver() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        drop(receiver);
+        assert!(Parser::new("a").send_to(&sender).is_err());
+    }
+
+    #[test]
+    fn single_line_setext_headings() {
+        let render = |text, options| {
+            let mut s = String::new();
+            crate::html::push_html(&mut s, Parser::new_ext(text, options));
+            s
+        };
+        let text = "a  \nb *c*\nd\\\n---\n\n> e\n> f\n> ===\n\ng\n===\n";
+        assert_eq!(
+            "<h2>a<br />\nb <em>c</em>\nd\\</h2>\n\
+             <blockquote>\n<h1>e\nf</h1>\n</blockquote>\n<h1>g</h1>\n",
+            render(text, Options::empty())
+        );
+        assert_eq!(
+            "<p>a<br />\nb <em>c</em></p>\n<h2>d\\</h2>\n\
+             <blockquote>\n<p>e</p>\n<h1>f</h1>\n</blockquote>\n<h1>g</h1>\n",
+            render(text, Options::ENABLE_SINGLE_LINE_SETEXT_HEADINGS)
+        );
+
+        let ranges: Vec<_> =
+            Parser::new_ext("a\nb\n==\n", Options::ENABLE_SINGLE_LINE_SETEXT_HEADINGS)
+                .into_offset_iter()
+                .filter_map(|(event, range)| match event {
+                    Event::Start(_) => Some(range),
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(vec![0..2, 2..7], ranges);
+    }
+
+    #[test]
+    fn fenced_code_block_closed_by_eof() {
+        for &(markdown, code) in &[
+            ("```\na\n\nb\n", "a\n\nb\n"),
+            ("```\na\n\nb", "a\n\nb"),
+            ("~~~~ rust\n```\na", "```\na"),
+            ("```\n", ""),
+            ("```", ""),
+        ] {
+            let mut text = String::new();
+            for (event, range) in Parser::new(markdown).into_offset_iter() {
+                match event {
+                    Event::Start(Tag::CodeBlock(..)) => assert_eq!(0..markdown.len(), range),
+                    Event::Text(t) => text.push_str(&t),
+                    _ => {}
+                }
+            }
+            assert_eq!(code, text);
+        }
+    }
+
+    #[test]
+    fn case_sensitive_references() {
+        let markdown = "[Foo] [foo] [FOO]\n\n[Foo]: /upper\n[foo]: /lower\n";
+        let dests = |parser: Parser| -> Vec<String> {
+            parser
+                .filter_map(|event| match event {
+                    Event::Start(Tag::Link(_, dest, _, _)) => Some(dest.to_string()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            vec!["/upper", "/upper", "/upper"],
+            dests(Parser::new(markdown))
+        );
+
+        let parser = Parser::new_ext(markdown, Options::ENABLE_CASE_SENSITIVE_REFERENCES);
+        assert_eq!(2, parser.reference_definitions().iter().count());
+        assert_eq!(
+            "/lower",
+            &*parser.reference_definitions().get("foo").unwrap().dest
+        );
+        assert_eq!(vec!["/upper", "/lower"], dests(parser));
+    }
+
+    #[test]
+    fn max_reference_definitions() {
+        let config = ParserConfig {
+            max_reference_definitions: Some(2),
+            ..Default::default()
+        };
+        let text = "[a]: /a
+[b]: /b
+[a]: /c
+[c]: /c
+
+[a] [b] [c]
+";
+        let parser = Parser::new_with_config(text, Options::empty(), config, None);
+        assert_eq!(2, parser.reference_definitions().iter().count());
+        let mut s = String::new();
+        crate::html::push_html(&mut s, parser);
+        assert_eq!(
+            "<p><a href=\"/a\">a</a>: /c\n[c]: /c</p>\n\
+             <p><a href=\"/a\">a</a> <a href=\"/b\">b</a> [c]</p>\n",
+            s
+        );
+    }
+
+    #[test]
+    fn reuse_parsed_blocks() {
+        let text = "# a\n\n> *b* [c]\n\n| d |\n|---|\n| `e` |\n\n[^f]: g\n";
+        let blocks = ParsedBlocks::new(text, Options::all());
+        let expected: Vec<_> = Parser::new_ext(text, Options::all())
+            .into_offset_iter()
+            .collect();
+        for _ in 0..2 {
+            let events: Vec<_> = Parser::from_parsed_blocks(&blocks, None)
+                .into_offset_iter()
+                .collect();
+            assert_eq!(expected, events);
+        }
+
+        let mut callback = |link: BrokenLink| Some((link.reference.to_string().into(), "".into()));
+        let linked = Parser::from_parsed_blocks(&blocks, Some(&mut callback))
+            .filter(|event| matches!(event, Event::Start(Tag::Link(..))))
+            .count();
+        assert_eq!(1, linked);
+    }
+
+    #[test]
+    fn extra_emphasis_delimiter() {
+        let render = |text: &str, delimiter: Option<char>| {
+            let config = ParserConfig {
+                extra_emphasis_delimiter: delimiter,
+                ..Default::default()
+            };
+            let mut s = String::new();
+            crate::html::push_html(
+                &mut s,
+                Parser::new_with_config(text, Options::empty(), config, None),
+            );
+            s
+        };
+        let text = "//a// /b/ ///c/// d//e// //*f* g//\n";
+        assert_eq!(
+            "<p><em>a</em> /b/ ///c/// d//e// <em><em>f</em> g</em></p>\n",
+            render(text, Some('/'))
+        );
+        assert_eq!(
+            "<p>//a// /b/ ///c/// d//e// //<em>f</em> g//</p>\n",
+            render(text, None)
+        );
+        assert_eq!(
+            "<p>http://example.com/ and <em>x</em></p>\n",
+            render("http://example.com/ and //x//\n", Some('/'))
+        );
+        // characters with another meaning are ignored
+        assert_eq!(
+            "<p>~~a~~ <em>b</em></p>\n",
+            render("~~a~~ *b*\n", Some('~'))
+        );
+
+        let tags: Vec<_> = Parser::new_with_config(
+            "==a==\n",
+            Options::empty(),
+            ParserConfig {
+                extra_emphasis_delimiter: Some('='),
+                ..Default::default()
+            },
+            None,
+        )
+        .filter(|event| matches!(event, Event::Start(..) | Event::End(..)))
+        .collect();
+        assert_eq!(
+            vec![
+                Event::Start(Tag::Paragraph),
+                Event::Start(Tag::ExtraEmphasis),
+                Event::End(Tag::ExtraEmphasis),
+                Event::End(Tag::Paragraph),
+            ],
+            tags
+        );
+    }
+
+    #[test]
+    fn blank_lines_with_carriage_returns() {
+        let render = |text: &str| {
+            let mut s = String::new();
+            crate::html::push_html(&mut s, Parser::new(text));
+            s
+        };
+        for &eol in &["\n", "\r\n", "\r"] {
+            let text = ["a", "", "b", " ", "- c", "", "- d", "", "> e", "", "f", ""].join(eol);
+            assert_eq!(
+                "<p>a</p>\n<p>b</p>\n<ul>\n<li>\n<p>c</p>\n</li>\n<li>\n<p>d</p>\n</li>\n</ul>\n\
+                 <blockquote>\n<p>e</p>\n</blockquote>\n<p>f</p>\n",
+                render(&text),
+                "line ending {:?}",
+                eol
+            );
+        }
+    }
+
+    #[test]
+    fn literal_angle_brackets_in_text() {
+        let text = "a < b <3 \\<div> &lt;i&gt; <not a tag <b>c</b>\n";
+        let mut literal = String::new();
+        let mut html = Vec::new();
+        for event in Parser::new(text) {
+            match event {
+                Event::Text(t) => literal.push_str(&t),
+                Event::Html(h) => html.push(h),
+                _ => {}
+            }
+        }
+        assert_eq!("a < b <3 <div> <i> <not a tag c", literal);
+        assert_eq!(vec![CowStr::from("<b>"), "</b>".into()], html);
+    }
+
+    #[test]
+    fn empty_and_blank_documents() {
+        for &text in &[
+            "", "   ", "\n", "\n\n\n", "   \n\n", "\r\n\r\n", "\r", "\t\n \t",
+        ] {
+            assert_eq!(0, Parser::new(text).count(), "{:?}", text);
+            assert_eq!(0, parser_with_extensions(text).into_offset_iter().count());
+
+            let mut s = String::new();
+            crate::html::push_html(&mut s, Parser::new_ext(text, Options::all()));
+            assert_eq!("", s);
+        }
+    }
+
+    #[test]
+    fn nul_replaced() {
+        let text = "a\0b `c\0` <x\0>\n\n<div\0>\n\n    d\0\n";
+        let mut s = String::new();
+        crate::html::push_html(&mut s, Parser::new(text));
+        assert_eq!(
+            "<p>a\u{FFFD}b <code>c\u{FFFD}</code> &lt;x\u{FFFD}&gt;</p>\n\
+             <div\u{FFFD}>\n<pre><code>d\u{FFFD}\n</code></pre>\n",
+            s
+        );
+    }
+
+    #[test]
+    fn nul_replaced_in_tags() {
+        let text = "```a\0b\n```\n\nc[^d\0] [e](f \"g\0\")\n\n[^d\0]: h\n\n# i {#j\0}\n";
+        let options = Options::ENABLE_FOOTNOTES | Options::ENABLE_HEADING_ATTRIBUTES;
+        let mut s = String::new();
+        crate::html::push_html(&mut s, Parser::new_ext(text, options));
+        assert!(!s.contains('\0'));
+        assert_eq!(
+            "<pre><code class=\"language-a\u{FFFD}b\"></code></pre>\n\
+             <p>c<sup class=\"footnote-reference\"><a href=\"#d\u{FFFD}\">1</a></sup> \
+             <a href=\"f\" title=\"g\u{FFFD}\">e</a></p>\n\
+             <div class=\"footnote-definition\" id=\"d\u{FFFD}\">\
+             <sup class=\"footnote-definition-label\">1</sup>\n<p>h</p>\n</div>\n\
+             <h1 id=\"j\u{FFFD}\">i</h1>\n",
+            s
+        );
+    }
+
+    #[test]
+    fn heading_attributes() {
+        let tags = |text| {
+            Parser::new_ext(text, Options::ENABLE_HEADING_ATTRIBUTES)
+                .filter_map(|event| match event {
+                    Event::Start(tag @ Tag::Heading(..)) => Some(tag),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![Tag::Heading(
+                HeadingLevel::H2,
+                Some("my-id".into()),
+                vec!["class1".into(), "class2".into()]
+            )],
+            tags("## Heading {#my-id .class1 .class2}\n")
+        );
+        assert_eq!(
+            vec![Tag::Heading(HeadingLevel::H1, Some("a_b".into()), vec![])],
+            tags("Setext *a* {#a_b}\n===\n")
+        );
+        assert_eq!(
+            vec![Tag::Heading(HeadingLevel::H1, None, vec![])],
+            tags("# a {#id\n")
+        );
+
+        let text: String = Parser::new_ext("# a {#id\n", Options::ENABLE_HEADING_ATTRIBUTES)
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.into_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!("a {#id", text);
+    }
 }
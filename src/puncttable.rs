@@ -33,7 +33,7 @@ const PUNCT_MASKS_ASCII: [u16; 8] = [
     0x7800, // U+0070...U+007F
 ];
 
-const PUNCT_TAB: [u16; 132] = [
+const PUNCT_TAB: [u16; 165] = [
     10,   // U+00A0...U+00AF
     11,   // U+00B0...U+00BF
     55,   // U+0370...U+037F
@@ -53,7 +53,11 @@ const PUNCT_TAB: [u16; 132] = [
     133,  // U+0850...U+085F
     150,  // U+0960...U+096F
     151,  // U+0970...U+097F
+    159,  // U+09F0...U+09FF
+    167,  // U+0A70...U+0A7F
     175,  // U+0AF0...U+0AFF
+    199,  // U+0C70...U+0C7F
+    200,  // U+0C80...U+0C8F
     223,  // U+0DF0...U+0DFF
     228,  // U+0E40...U+0E4F
     229,  // U+0E50...U+0E5F
@@ -77,6 +81,7 @@ const PUNCT_TAB: [u16; 132] = [
     426,  // U+1AA0...U+1AAF
     437,  // U+1B50...U+1B5F
     438,  // U+1B60...U+1B6F
+    439,  // U+1B70...U+1B7F
     447,  // U+1BF0...U+1BFF
     451,  // U+1C30...U+1C3F
     455,  // U+1C70...U+1C7F
@@ -106,6 +111,7 @@ const PUNCT_TAB: [u16; 132] = [
     738,  // U+2E20...U+2E2F
     739,  // U+2E30...U+2E3F
     740,  // U+2E40...U+2E4F
+    741,  // U+2E50...U+2E5F
     768,  // U+3000...U+300F
     769,  // U+3010...U+301F
     771,  // U+3030...U+303F
@@ -150,25 +156,52 @@ const PUNCT_TAB: [u16; 132] = [
     4271, // U+10AF0...U+10AFF
     4275, // U+10B30...U+10B3F
     4281, // U+10B90...U+10B9F
+    4330, // U+10EA0...U+10EAF
+    4341, // U+10F50...U+10F5F
+    4344, // U+10F80...U+10F8F
     4356, // U+11040...U+1104F
     4363, // U+110B0...U+110BF
     4364, // U+110C0...U+110CF
     4372, // U+11140...U+1114F
     4375, // U+11170...U+1117F
     4380, // U+111C0...U+111CF
+    4381, // U+111D0...U+111DF
     4387, // U+11230...U+1123F
+    4394, // U+112A0...U+112AF
+    4420, // U+11440...U+1144F
+    4421, // U+11450...U+1145F
     4428, // U+114C0...U+114CF
     4444, // U+115C0...U+115CF
+    4445, // U+115D0...U+115DF
     4452, // U+11640...U+1164F
+    4454, // U+11660...U+1166F
+    4459, // U+116B0...U+116BF
+    4467, // U+11730...U+1173F
+    4483, // U+11830...U+1183F
+    4500, // U+11940...U+1194F
+    4510, // U+119E0...U+119EF
+    4515, // U+11A30...U+11A3F
+    4516, // U+11A40...U+11A4F
+    4521, // U+11A90...U+11A9F
+    4522, // U+11AA0...U+11AAF
+    4548, // U+11C40...U+11C4F
+    4551, // U+11C70...U+11C7F
+    4591, // U+11EF0...U+11EFF
+    4607, // U+11FF0...U+11FFF
     4679, // U+12470...U+1247F
+    4863, // U+12FF0...U+12FFF
     5798, // U+16A60...U+16A6F
     5807, // U+16AF0...U+16AFF
     5811, // U+16B30...U+16B3F
     5812, // U+16B40...U+16B4F
+    5865, // U+16E90...U+16E9F
+    5886, // U+16FE0...U+16FEF
     7113, // U+1BC90...U+1BC9F
+    7592, // U+1DA80...U+1DA8F
+    7829, // U+1E950...U+1E95F
 ];
 
-const PUNCT_MASKS: [u16; 132] = [
+const PUNCT_MASKS: [u16; 165] = [
     0x0882, // U+00A0...U+00AF
     0x88c0, // U+00B0...U+00BF
     0x4000, // U+0370...U+037F
@@ -179,7 +212,7 @@ const PUNCT_MASKS: [u16; 132] = [
     0x0049, // U+05C0...U+05CF
     0x0018, // U+05F0...U+05FF
     0x3600, // U+0600...U+060F
-    0xc800, // U+0610...U+061F
+    0xe800, // U+0610...U+061F
     0x3c00, // U+0660...U+066F
     0x0010, // U+06D0...U+06DF
     0x3fff, // U+0700...U+070F
@@ -188,7 +221,11 @@ const PUNCT_MASKS: [u16; 132] = [
     0x4000, // U+0850...U+085F
     0x0030, // U+0960...U+096F
     0x0001, // U+0970...U+097F
+    0x2000, // U+09F0...U+09FF
+    0x0040, // U+0A70...U+0A7F
     0x0001, // U+0AF0...U+0AFF
+    0x0080, // U+0C70...U+0C7F
+    0x0010, // U+0C80...U+0C8F
     0x0010, // U+0DF0...U+0DFF
     0x8000, // U+0E40...U+0E4F
     0x0c00, // U+0E50...U+0E5F
@@ -201,7 +238,7 @@ const PUNCT_MASKS: [u16; 132] = [
     0x0800, // U+10F0...U+10FF
     0x01ff, // U+1360...U+136F
     0x0001, // U+1400...U+140F
-    0x6000, // U+1660...U+166F
+    0x4000, // U+1660...U+166F
     0x1800, // U+1690...U+169F
     0x3800, // U+16E0...U+16EF
     0x0060, // U+1730...U+173F
@@ -212,6 +249,7 @@ const PUNCT_MASKS: [u16; 132] = [
     0x3f7f, // U+1AA0...U+1AAF
     0xfc00, // U+1B50...U+1B5F
     0x0001, // U+1B60...U+1B6F
+    0x6000, // U+1B70...U+1B7F
     0xf000, // U+1BF0...U+1BFF
     0xf800, // U+1C30...U+1C3F
     0xc000, // U+1C70...U+1C7F
@@ -240,7 +278,8 @@ const PUNCT_MASKS: [u16; 132] = [
     0xffff, // U+2E10...U+2E1F
     0x7fff, // U+2E20...U+2E2F
     0xffff, // U+2E30...U+2E3F
-    0x0007, // U+2E40...U+2E4F
+    0xffff, // U+2E40...U+2E4F
+    0x3ffc, // U+2E50...U+2E5F
     0xff0e, // U+3000...U+300F
     0xfff3, // U+3010...U+301F
     0x2001, // U+3030...U+303F
@@ -252,7 +291,7 @@ const PUNCT_MASKS: [u16; 132] = [
     0x00fc, // U+A6F0...U+A6FF
     0x00f0, // U+A870...U+A87F
     0xc000, // U+A8C0...U+A8CF
-    0x0700, // U+A8F0...U+A8FF
+    0x1700, // U+A8F0...U+A8FF
     0xc000, // U+A920...U+A92F
     0x8000, // U+A950...U+A95F
     0x3ffe, // U+A9C0...U+A9CF
@@ -285,22 +324,49 @@ const PUNCT_MASKS: [u16; 132] = [
     0x007f, // U+10AF0...U+10AFF
     0xfe00, // U+10B30...U+10B3F
     0x1e00, // U+10B90...U+10B9F
+    0x2000, // U+10EA0...U+10EAF
+    0x03e0, // U+10F50...U+10F5F
+    0x03c0, // U+10F80...U+10F8F
     0x3f80, // U+11040...U+1104F
     0xd800, // U+110B0...U+110BF
     0x0003, // U+110C0...U+110CF
     0x000f, // U+11140...U+1114F
     0x0030, // U+11170...U+1117F
     0x21e0, // U+111C0...U+111CF
+    0xe800, // U+111D0...U+111DF
     0x3f00, // U+11230...U+1123F
+    0x0200, // U+112A0...U+112AF
+    0xf800, // U+11440...U+1144F
+    0x2c00, // U+11450...U+1145F
     0x0040, // U+114C0...U+114CF
-    0x03fe, // U+115C0...U+115CF
+    0xfffe, // U+115C0...U+115CF
+    0x00ff, // U+115D0...U+115DF
     0x000e, // U+11640...U+1164F
+    0x1fff, // U+11660...U+1166F
+    0x0200, // U+116B0...U+116BF
+    0x7000, // U+11730...U+1173F
+    0x0800, // U+11830...U+1183F
+    0x0070, // U+11940...U+1194F
+    0x0004, // U+119E0...U+119EF
+    0x8000, // U+11A30...U+11A3F
+    0x007f, // U+11A40...U+11A4F
+    0xdc00, // U+11A90...U+11A9F
+    0x0007, // U+11AA0...U+11AAF
+    0x003e, // U+11C40...U+11C4F
+    0x0003, // U+11C70...U+11C7F
+    0x0180, // U+11EF0...U+11EFF
+    0x8000, // U+11FF0...U+11FFF
     0x001f, // U+12470...U+1247F
+    0x0006, // U+12FF0...U+12FFF
     0xc000, // U+16A60...U+16A6F
     0x0020, // U+16AF0...U+16AFF
     0x0f80, // U+16B30...U+16B3F
     0x0010, // U+16B40...U+16B4F
+    0x0780, // U+16E90...U+16E9F
+    0x0004, // U+16FE0...U+16FEF
     0x8000, // U+1BC90...U+1BC9F
+    0x0f80, // U+1DA80...U+1DA8F
+    0xc000, // U+1E950...U+1E95F
 ];
 
 pub(crate) fn is_ascii_punctuation(c: u8) -> bool {
@@ -312,7 +378,7 @@ pub(crate) fn is_punctuation(c: char) -> bool {
     if cp < 128 {
         return is_ascii_punctuation(cp as u8);
     }
-    if cp > 0x1BC9F {
+    if cp > 0x1E95F {
         return false;
     }
     let high = (cp / 16) as u16;
@@ -344,8 +410,11 @@ mod tests {
 
         assert!(is_punctuation('\u{00A1}'));
         assert!(is_punctuation('\u{060C}'));
+        assert!(is_punctuation('\u{061D}'));
         assert!(is_punctuation('\u{FF65}'));
         assert!(is_punctuation('\u{1BC9F}'));
         assert!(!is_punctuation('\u{1BCA0}'));
+        assert!(is_punctuation('\u{1E95F}'));
+        assert!(!is_punctuation('\u{1E960}'));
     }
 }
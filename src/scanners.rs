@@ -204,8 +204,9 @@ impl<'a> LineStart<'a> {
     ///
     /// Return value is the character, the start index, and the indent in spaces.
     /// For ordered list markers, the character will be one of b'.' or b')'. For
-    /// bullet list markers, it will be one of b'-', b'+', or b'*'.
-    pub(crate) fn scan_list_marker(&mut self) -> Option<(u8, u64, usize)> {
+    /// bullet list markers, it will be one of b'-', b'+', or b'*'. A line that
+    /// is a thematic break of at least `hrule_min` characters is not a list item.
+    pub(crate) fn scan_list_marker(&mut self, hrule_min: usize) -> Option<(u8, u64, usize)> {
         let save = self.clone();
         let indent = self.scan_space_upto(4);
         if indent < 4 && self.ix < self.bytes.len() {
@@ -213,7 +214,7 @@ impl<'a> LineStart<'a> {
             if c == b'-' || c == b'+' || c == b'*' {
                 if self.ix >= self.min_hrule_offset {
                     // there could be an hrule here
-                    if let Err(min_offset) = scan_hrule(&self.bytes[self.ix..]) {
+                    if let Err(min_offset) = scan_hrule(&self.bytes[self.ix..], hrule_min) {
                         self.min_hrule_offset = min_offset;
                     } else {
                         *self = save;
@@ -273,7 +274,8 @@ impl<'a> LineStart<'a> {
     }
 
     /// Returns Some(is_checked) when a task list marker was found. Resets itself
-    /// to original state otherwise.
+    /// to original state otherwise. This is only tried directly after a list
+    /// item marker, so a task list marker must be the first thing in the item.
     pub(crate) fn scan_task_list_marker(&mut self) -> Option<bool> {
         let save = self.clone();
         self.scan_space_upto(3);
@@ -458,15 +460,15 @@ fn calc_indent(text: &[u8], max: usize) -> (usize, usize) {
     (offset, spaces)
 }
 
-/// Scan hrule opening sequence.
+/// Scan hrule opening sequence of at least `min_len` characters.
 ///
 /// Returns Ok(x) when it finds an hrule, where x is the
 /// size of line containing the hrule, including the trailing newline.
 ///
 /// Returns Err(x) when it does not find an hrule and x is
 /// the offset in data before no hrule can appear.
-pub(crate) fn scan_hrule(bytes: &[u8]) -> Result<usize, usize> {
-    if bytes.len() < 3 {
+pub(crate) fn scan_hrule(bytes: &[u8], min_len: usize) -> Result<usize, usize> {
+    if bytes.len() < min_len {
         return Err(0);
     }
     let c = bytes[0];
@@ -490,7 +492,7 @@ pub(crate) fn scan_hrule(bytes: &[u8]) -> Result<usize, usize> {
         }
         i += 1;
     }
-    if n >= 3 {
+    if n >= min_len {
         Ok(i)
     } else {
         Err(i)
@@ -509,6 +511,17 @@ pub(crate) fn scan_atx_heading(data: &[u8]) -> Option<HeadingLevel> {
     }
 }
 
+/// Checks whether an ATX heading has no content, like `#` or `## ##`.
+/// Assumes `data` starts with an opening sequence.
+pub(crate) fn is_empty_atx_heading(data: &[u8]) -> bool {
+    let start = scan_ch_repeat(data, b'#');
+    let line = &data[start..start + scan_while(&data[start..], |b| b != b'\n' && b != b'\r')];
+    let content = &line[scan_whitespace_no_nl(line)..];
+    let content = &content[..content.len() - scan_rev_while(content, is_ascii_whitespace_no_nl)];
+    // only a closing sequence can be left
+    content.iter().all(|&b| b == b'#')
+}
+
 /// Scan a setext heading underline.
 ///
 /// Returns number of bytes in line (including trailing newline) and level.
@@ -1203,6 +1216,127 @@ fn scan_email(text: &str, start_ix: usize) -> Option<(usize, CowStr<'_>)> {
     Some((start_ix + i + 1, text[start_ix..(start_ix + i)].into()))
 }
 
+/// Scans an extended autolink as in GitHub Flavored Markdown, a URL starting
+/// with `http://`, `https://` or `www.` that isn't enclosed in angle brackets.
+/// The link ends at whitespace, `<` or a `]` without a matching `[` in the
+/// link, such as the end of link text, and at `|` if `stop_at_pipe` is set.
+/// Trailing punctuation and unbalanced closing parentheses are left out.
+///
+/// Returns the length of the link.
+pub(crate) fn scan_extended_url(bytes: &[u8], stop_at_pipe: bool) -> Option<usize> {
+    let (domain_start, allow_short) = if bytes.starts_with(b"www.") {
+        (0, false)
+    } else if bytes.starts_with(b"http://") {
+        (7, true)
+    } else if bytes.starts_with(b"https://") {
+        (8, true)
+    } else {
+        return None;
+    };
+    let domain_end = domain_start + scan_extended_domain(&bytes[domain_start..], allow_short)?;
+    let mut brackets = 0;
+    let end = domain_end
+        + scan_while(&bytes[domain_end..], |c| match c {
+            b'[' => {
+                brackets += 1;
+                true
+            }
+            b']' if brackets == 0 => false,
+            b']' => {
+                brackets -= 1;
+                true
+            }
+            _ => !is_ascii_whitespace(c) && c != b'<' && !(stop_at_pipe && c == b'|'),
+        });
+    let end = trim_extended_url(&bytes[..end]);
+    if end > domain_start {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Scans the domain of an extended autolink: segments of alphanumeric
+/// characters, `_` and `-` separated by periods, with no `_` in the last two
+/// segments. Unless `allow_short` is set, there must be at least one period.
+/// Returns the length of the domain, including any trailing periods.
+fn scan_extended_domain(bytes: &[u8], allow_short: bool) -> Option<usize> {
+    let len = scan_while(bytes, is_extended_domain_char);
+    let domain = &bytes[..len - scan_rev_while(&bytes[..len], |c| c == b'.')];
+    let underscore_at_end = domain
+        .rsplit(|&c| c == b'.')
+        .take(2)
+        .any(|segment| segment.contains(&b'_'));
+    if domain.is_empty() || underscore_at_end || !(allow_short || domain.contains(&b'.')) {
+        return None;
+    }
+    Some(len)
+}
+
+/// Returns the length of an extended autolink without its trailing
+/// punctuation, unbalanced closing parentheses and entity reference.
+fn trim_extended_url(link: &[u8]) -> usize {
+    let opening = link.iter().filter(|&&c| c == b'(').count();
+    let mut closing = link.iter().filter(|&&c| c == b')').count();
+    let mut end = link.len();
+    while end > 0 {
+        match link[end - 1] {
+            b'?' | b'!' | b'.' | b',' | b':' | b'*' | b'_' | b'~' | b'\'' | b'"' => end -= 1,
+            b')' if closing > opening => {
+                closing -= 1;
+                end -= 1;
+            }
+            b';' => {
+                let name_len = scan_rev_while(&link[..end - 1], is_ascii_alphanumeric);
+                let amp = end - 1 - name_len;
+                if name_len > 0 && amp > 0 && link[amp - 1] == b'&' {
+                    end = amp - 1;
+                } else {
+                    break;
+                }
+            }
+            _ => break,
+        }
+    }
+    end
+}
+
+/// Whether the byte may appear in the domain of an extended autolink.
+pub(crate) fn is_extended_domain_char(c: u8) -> bool {
+    is_ascii_alphanumeric(c) || c == b'_' || c == b'-' || c == b'.'
+}
+
+/// Whether the byte may appear in the local part of an extended email
+/// autolink.
+pub(crate) fn is_extended_email_local(c: u8) -> bool {
+    is_ascii_alphanumeric(c) || c == b'.' || c == b'+' || c == b'-' || c == b'_'
+}
+
+/// Scans an extended email autolink as in GitHub Flavored Markdown, an email
+/// address that isn't enclosed in angle brackets. The domain can't end with
+/// `-` or `_`, and a trailing period isn't part of the address.
+///
+/// Returns the length of the address.
+pub(crate) fn scan_extended_email(bytes: &[u8]) -> Option<usize> {
+    let local_len = scan_while(bytes, is_extended_email_local);
+    if local_len == 0 || scan_ch(&bytes[local_len..], b'@') == 0 {
+        return None;
+    }
+    let domain_start = local_len + 1;
+    let len = scan_while(&bytes[domain_start..], is_extended_domain_char);
+    let domain = &bytes[domain_start..domain_start + len];
+    let domain = &domain[..domain.len() - scan_rev_while(domain, |c| c == b'.')];
+    match domain.last() {
+        Some(&c) if c != b'-' && c != b'_' && domain.contains(&b'.') => {
+            if domain[0] == b'.' || domain.windows(2).any(|pair| pair == b"..") {
+                return None;
+            }
+            Some(domain_start + domain.len())
+        }
+        _ => None,
+    }
+}
+
 /// Scan comment, declaration, or CDATA section, with initial "<!" already consumed.
 /// Returns byte offset on match.
 pub(crate) fn scan_inline_html_comment(
@@ -1304,4 +1438,111 @@ mod test {
     fn overflow_by_addition() {
         assert!(scan_listitem(b"1844674407370955161615!").is_none());
     }
+
+    #[test]
+    fn autolink_query_and_fragment() {
+        // scanning starts after the opening angle bracket
+        let text = "<http://a.b/c?d=e&f=g#h>";
+        let (ix, uri, link_type) = scan_autolink(text, 1).unwrap();
+        assert_eq!(text.len(), ix);
+        assert_eq!("http://a.b/c?d=e&f=g#h", uri.as_ref());
+        assert_eq!(LinkType::Autolink, link_type);
+    }
+
+    #[test]
+    fn autolink_punctuation() {
+        let text = "<https://x.y/p;q=1,2(3)*[4]!$'~@:+%20>";
+        let (_, uri, _) = scan_autolink(text, 1).unwrap();
+        assert_eq!(&text[1..text.len() - 1], uri.as_ref());
+    }
+
+    #[test]
+    fn autolink_rejects_whitespace_and_lt() {
+        assert!(scan_autolink("<http://a b>", 1).is_none());
+        assert!(scan_autolink("<http://a\nb>", 1).is_none());
+        assert!(scan_autolink("<http://a<b>", 1).is_none());
+    }
+
+    #[test]
+    fn link_dest_line_endings() {
+        assert_eq!(None, scan_link_dest("<a\nb>", 0, 32));
+        assert_eq!(None, scan_link_dest("<a\r\nb>", 0, 32));
+        assert_eq!(Some((5, "a b")), scan_link_dest("<a b>\n", 0, 32));
+        assert_eq!(Some((1, "a")), scan_link_dest("a\nb", 0, 32));
+        assert_eq!(Some((1, "a")), scan_link_dest("a b", 0, 32));
+    }
+
+    #[test]
+    fn named_entities() {
+        let entity = |text: &str| {
+            let (n, value) = scan_entity(text.as_bytes());
+            (n, value.map(|value| value.into_string()))
+        };
+        assert_eq!((7, Some("\u{2266}\u{0338}".to_string())), entity("&nleqq;"));
+        assert_eq!(
+            (33, Some("\u{2233}".to_string())),
+            entity("&CounterClockwiseContourIntegral;")
+        );
+        assert_eq!((5, Some("\u{1D504}".to_string())), entity("&Afr;x"));
+        assert_eq!((0, None), entity("&foo;"));
+        // the semicolon is required and names are case sensitive
+        assert_eq!((0, None), entity("&amp"));
+        assert_eq!((0, None), entity("&NLEQQ;"));
+    }
+
+    #[test]
+    fn autolink_scheme_case() {
+        for &(text, uri) in &[("<HTTP://x>", "HTTP://x"), ("<MailTo:a@b>", "MailTo:a@b")] {
+            let (ix, scanned, link_type) = scan_autolink(text, 1).unwrap();
+            assert_eq!(text.len(), ix);
+            assert_eq!(uri, scanned.as_ref());
+            assert_eq!(LinkType::Autolink, link_type);
+        }
+    }
+
+    #[test]
+    fn blank_line_endings() {
+        for &text in &["\n", "\r\n", "\r", " \t\r", "  \r\n"] {
+            assert_eq!(Some(text.len()), scan_blank_line(text.as_bytes()));
+        }
+        assert_eq!(Some(1), scan_blank_line(b"\r\r"));
+        assert_eq!(None, scan_blank_line(b" a\r"));
+    }
+
+    #[test]
+    fn extended_url_trailing_characters() {
+        fn url(text: &str) -> Option<&str> {
+            scan_extended_url(text.as_bytes(), false).map(|n| &text[..n])
+        }
+        assert_eq!(Some("www.example.com"), url("www.example.com)"));
+        assert_eq!(Some("https://ex.com/a_(b)"), url("https://ex.com/a_(b)"));
+        assert_eq!(Some("https://ex.com/a_(b)"), url("https://ex.com/a_(b))."));
+        assert_eq!(Some("www.a.com/?q=1"), url("www.a.com/?q=1?!,:*_~"));
+        assert_eq!(Some("www.a.com/x"), url("www.a.com/x&hl; y"));
+        assert_eq!(Some("www.a.com/x&hl"), url("www.a.com/x&hl<b>"));
+        assert_eq!(Some("http://localhost"), url("http://localhost"));
+        assert_eq!(None, url("www.a.b_c"));
+        assert_eq!(None, url("www._"));
+        assert_eq!(None, url("ftp://a.com"));
+
+        let with_pipe = scan_extended_url(b"https://x.com|y", true);
+        assert_eq!(Some(13), with_pipe);
+    }
+
+    #[test]
+    fn extended_email() {
+        fn email(text: &str) -> Option<&str> {
+            scan_extended_email(text.as_bytes()).map(|n| &text[..n])
+        }
+        assert_eq!(Some("a.b-c_d@a.b"), email("a.b-c_d@a.b"));
+        assert_eq!(Some("a.b-c_d@a.b"), email("a.b-c_d@a.b."));
+        assert_eq!(
+            Some("hello+xyz@mail.example"),
+            email("hello+xyz@mail.example")
+        );
+        assert_eq!(None, email("a.b-c_d@a.b-"));
+        assert_eq!(None, email("a.b-c_d@a.b_"));
+        assert_eq!(None, email("hello@mail+xyz.example"));
+        assert_eq!(None, email("a@b"));
+    }
 }
@@ -0,0 +1,249 @@
+//! Utilities for the plain text content of a document.
+
+use crate::{Event, LinkType, Tag};
+
+/// Configuration for [`word_count`], toggling which parts of the document are
+/// counted. The default counts all visible text except image descriptions.
+#[derive(Clone, Debug, Default)]
+pub struct WordCountConfig {
+    /// Don't count the contents of code blocks. Inline code is always counted.
+    pub exclude_code_blocks: bool,
+    /// Count the alternative text of images.
+    pub include_image_alt: bool,
+}
+
+/// Number of words and characters in the plain text of a document.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WordCount {
+    /// Number of whitespace separated words.
+    pub words: usize,
+    /// Number of characters, including spaces within a line but not line
+    /// breaks or the boundaries between blocks.
+    pub chars: usize,
+}
+
+/// Counts the words and characters of the plain text in an event stream.
+/// Markup, raw HTML and the URLs of autolinks are not counted, nor are the
+/// destinations and titles of links, which aren't part of the text anyway.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::{text, Parser};
+///
+/// let markdown = "Some *emphasized* text with [a link](https://example.com).";
+/// let count = text::word_count(Parser::new(markdown), &Default::default());
+///
+/// assert_eq!(count.words, 6);
+/// ```
+pub fn word_count<'a, I>(iter: I, config: &WordCountConfig) -> WordCount
+where
+    I: IntoIterator<Item = Event<'a>>,
+{
+    let mut count = WordCount::default();
+    let mut in_word = false;
+    let mut hidden = 0;
+
+    for event in iter {
+        match event {
+            Event::Start(ref tag) if is_hidden(tag, config) => {
+                hidden += 1;
+                in_word = false;
+            }
+            Event::End(ref tag) if is_hidden(tag, config) => {
+                hidden -= 1;
+                in_word = false;
+            }
+            Event::Start(Tag::Emphasis)
+            | Event::End(Tag::Emphasis)
+            | Event::Start(Tag::Strong)
+            | Event::End(Tag::Strong)
+            | Event::Start(Tag::Strikethrough)
+            | Event::End(Tag::Strikethrough)
+            | Event::Start(Tag::ExtraEmphasis)
+            | Event::End(Tag::ExtraEmphasis)
+            | Event::Start(Tag::Link(..))
+            | Event::End(Tag::Link(..)) => {}
+            Event::Text(text) | Event::Code(text) if hidden == 0 => {
+                for c in text.chars() {
+                    if c.is_whitespace() {
+                        in_word = false;
+                        if c != '\n' {
+                            count.chars += 1;
+                        }
+                    } else {
+                        if !in_word {
+                            count.words += 1;
+                            in_word = true;
+                        }
+                        count.chars += 1;
+                    }
+                }
+            }
+            Event::Html(..)
+            | Event::InlineHtml(..)
+            | Event::FootnoteReference(..)
+            | Event::TaskListMarker(..) => {}
+            _ => in_word = false,
+        }
+    }
+
+    count
+}
+
+fn is_hidden(tag: &Tag, config: &WordCountConfig) -> bool {
+    match tag {
+        Tag::CodeBlock(..) => config.exclude_code_blocks,
+        Tag::Image(..) => !config.include_image_alt,
+        Tag::Link(LinkType::Autolink, ..) | Tag::Link(LinkType::Email, ..) => true,
+        _ => false,
+    }
+}
+
+/// Kind of line ending.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// `\r`
+    Cr,
+}
+
+impl LineEnding {
+    /// Returns the characters of the line ending.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+/// Returns the kind of line ending used most in the text. Ties are decided in
+/// favor of `Lf`, then `CrLf`, and text without line endings gives `Lf`.
+///
+/// # Examples
+///
+/// ```
+/// use pulldown_cmark::text::{detect_line_ending, LineEnding};
+///
+/// assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::CrLf);
+/// ```
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0, 0, 0);
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => lf += 1,
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if lf >= crlf && lf >= cr {
+        LineEnding::Lf
+    } else if crlf >= cr {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Cr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{detect_line_ending, word_count, LineEnding, WordCount, WordCountConfig};
+    use crate::Parser;
+
+    fn count(text: &str, config: &WordCountConfig) -> WordCount {
+        word_count(Parser::new(text), config)
+    }
+
+    #[test]
+    fn emphasis_does_not_split_words() {
+        let result = count("foo*bar* **baz** qux", &Default::default());
+        assert_eq!(
+            WordCount {
+                words: 3,
+                chars: 14
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn blocks_and_breaks_separate_words() {
+        let result = count("# foo\nbar\nbaz\n\n- qux", &Default::default());
+        assert_eq!(
+            WordCount {
+                words: 4,
+                chars: 12
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn links_count_only_text() {
+        let result = count(
+            "[two words](https://example.com \"title\") <https://example.com>",
+            &Default::default(),
+        );
+        assert_eq!(
+            WordCount {
+                words: 2,
+                chars: 10
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn html_not_counted() {
+        let result = count("a <b>bold</b> move", &Default::default());
+        assert_eq!(3, result.words);
+    }
+
+    #[test]
+    fn code() {
+        let text = "Call `foo()` like so:\n\n```\nfoo(bar)\nbaz()\n```\n";
+        assert_eq!(6, count(text, &Default::default()).words);
+
+        let config = WordCountConfig {
+            exclude_code_blocks: true,
+            include_image_alt: false,
+        };
+        assert_eq!(4, count(text, &config).words);
+    }
+
+    #[test]
+    fn image_alt() {
+        let text = "look: ![a *nice* picture](pic.png)";
+        assert_eq!(1, count(text, &Default::default()).words);
+
+        let config = WordCountConfig {
+            exclude_code_blocks: false,
+            include_image_alt: true,
+        };
+        assert_eq!(4, count(text, &config).words);
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(LineEnding::Lf, detect_line_ending("a\nb\r\nc\n"));
+        assert_eq!(LineEnding::CrLf, detect_line_ending("a\r\nb\r\nc\n\r"));
+        assert_eq!(LineEnding::Cr, detect_line_ending("a\rb\rc\r\n"));
+        // ties and text without line endings
+        assert_eq!(LineEnding::Lf, detect_line_ending("a\r\nb\n"));
+        assert_eq!(LineEnding::CrLf, detect_line_ending("a\r\nb\r"));
+        assert_eq!(LineEnding::Lf, detect_line_ending("a"));
+    }
+}
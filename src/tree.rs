@@ -60,7 +60,7 @@ impl<T: Default> Tree<T> {
     // The alternative would be subtracting one from every TreeIndex
     // every time we convert it to usize to index our nodes.
     pub(crate) fn with_capacity(cap: usize) -> Tree<T> {
-        let mut nodes = Vec::with_capacity(cap);
+        let mut nodes = Vec::with_capacity(cap + 1);
         nodes.push(Node {
             child: None,
             next: None,
@@ -119,6 +119,32 @@ impl<T: Default> Tree<T> {
         ix
     }
 
+    /// Removes the current node along with its children. It must be the last
+    /// node appended to the tree apart from its descendants, and `prev` the
+    /// sibling preceding it, if any.
+    pub(crate) fn remove_cur(&mut self, prev: Option<TreeIndex>) {
+        let cur_ix = self.cur.unwrap();
+        self.nodes.truncate(cur_ix.get());
+        if let Some(prev_ix) = prev {
+            self[prev_ix].next = None;
+        } else if let Some(&parent) = self.spine.last() {
+            self[parent].child = None;
+        }
+        self.cur = prev;
+    }
+
+    /// Drops the siblings following `prev` from the children of the parent
+    /// node, or all of the children if `prev` is `None`, and moves focus to
+    /// `prev`. The dropped nodes are only unlinked, and stay in the arena.
+    pub(crate) fn truncate_siblings(&mut self, prev: Option<TreeIndex>) {
+        if let Some(prev_ix) = prev {
+            self[prev_ix].next = None;
+        } else if let Some(&parent) = self.spine.last() {
+            self[parent].child = None;
+        }
+        self.cur = prev;
+    }
+
     /// Look at the parent node.
     pub(crate) fn peek_up(&self) -> Option<TreeIndex> {
         self.spine.last().copied()
@@ -139,6 +165,19 @@ impl<T: Default> Tree<T> {
         self.nodes.len() <= 1
     }
 
+    /// Returns the number of nodes in the tree, not counting the root node.
+    #[cfg(feature = "stats")]
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// Returns the number of nodes the tree has room for without
+    /// reallocating, not counting the root node.
+    #[cfg(feature = "stats")]
+    pub(crate) fn capacity(&self) -> usize {
+        self.nodes.capacity() - 1
+    }
+
     /// Returns the length of the spine.
     pub(crate) fn spine_len(&self) -> usize {
         self.spine.len()
@@ -1,6 +1,6 @@
 // Tests for HTML spec.
 
-use pulldown_cmark::{html, BrokenLink, Options, Parser};
+use pulldown_cmark::{html, BrokenLink, Event, Options, Parser, Tag};
 
 #[test]
 fn html_test_1() {
@@ -248,3 +248,809 @@ fn html_test_broken_callback() {
 
     assert_eq!(expected, s);
 }
+
+#[test]
+fn html_test_table_roles() {
+    let original = "A | B\n---|:-:\nfoo | bar";
+    let plain = r##"<table><thead><tr><th>A</th><th align="center">B</th></tr></thead><tbody>
+<tr><td>foo</td><td align="center">bar</td></tr>
+</tbody></table>
+"##;
+    let accessible = r##"<table role="table"><thead><tr role="row"><th scope="col">A</th><th scope="col" align="center">B</th></tr></thead><tbody>
+<tr role="row"><td>foo</td><td align="center">bar</td></tr>
+</tbody></table>
+"##;
+
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_TABLES),
+        html::HtmlConfig::default(),
+    );
+    assert_eq!(plain, s);
+
+    let config = html::HtmlConfig {
+        table_roles: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_TABLES),
+        config,
+    );
+    assert_eq!(accessible, s);
+}
+
+#[test]
+fn html_test_disable_tilde_fences() {
+    let original = "~~~\nfoo\n~~~\n\n```\nbar\n```\n";
+    let expected = "<p>~~~\nfoo\n~~~</p>\n<pre><code>bar\n</code></pre>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::DISABLE_TILDE_FENCES),
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_disabled_fence_does_not_interrupt_paragraph() {
+    let original = "foo\n~~~\nbar\n~~~\n";
+    let expected = "<p>foo\n~~~\nbar\n~~~</p>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::DISABLE_TILDE_FENCES),
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_disable_backtick_fences() {
+    let original = "```\nfoo\n```\n\n~~~\nbar\n~~~\n";
+    let expected = "<p><code>foo</code></p>\n<pre><code>bar\n</code></pre>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::DISABLE_BACKTICK_FENCES),
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_collapse_breaks() {
+    let original = "foo\\\n\\\n\\\nbar\n\n```\na\n\n\nb\n```\n";
+    let expected = "<p>foo<br />\nbar</p>\n<pre><code>a\n\n\nb\n</code></pre>\n";
+
+    let config = html::HtmlConfig {
+        collapse_breaks: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_collapse_soft_and_hard_breaks() {
+    let original = "foo\n\\\nbar\nbaz\n";
+    let expected = "<p>foo<br />\nbar\nbaz</p>\n";
+
+    let config = html::HtmlConfig {
+        collapse_breaks: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_figures() {
+    let original = r##"![alt *text*](/img.png "A title")
+
+![no title](/img.png)
+
+Text ![alt](/img.png "A title")
+
+![alt](/img.png "A title") text
+"##;
+    let expected = r##"<figure><img src="/img.png" alt="alt text" title="A title" /><figcaption>A title</figcaption></figure>
+<p><img src="/img.png" alt="no title" /></p>
+<p>Text <img src="/img.png" alt="alt" title="A title" /></p>
+<p><img src="/img.png" alt="alt" title="A title" /> text</p>
+"##;
+
+    let config = html::HtmlConfig {
+        figures: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_line_continuations() {
+    let original = "foo\\\n  bar\\\nbaz  \nqux\n\nfoo\\\n===\n";
+    let hard_breaks = "<p>foo<br />\nbar<br />\nbaz<br />\nqux</p>\n<h1>foo\\</h1>\n";
+    let continuations = "<p>foobarbaz<br />\nqux</p>\n<h1>foo\\</h1>\n";
+
+    let mut s = String::new();
+    html::push_html(&mut s, Parser::new_ext(original, Options::empty()));
+    assert_eq!(hard_breaks, s);
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_LINE_CONTINUATIONS),
+    );
+    assert_eq!(continuations, s);
+}
+
+#[test]
+fn html_test_inline_alignment_styles() {
+    let original = "a | b | c\n:-:|--:|---\nd | e | f\n";
+    let expected = r##"<table><thead><tr><th style="text-align: center">a</th><th style="text-align: right">b</th><th>c</th></tr></thead><tbody>
+<tr><td style="text-align: center">d</td><td style="text-align: right">e</td><td>f</td></tr>
+</tbody></table>
+"##;
+
+    let config = html::HtmlConfig {
+        inline_alignment_styles: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_TABLES),
+        config,
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_splice_fragment_with_shared_reference_definitions() {
+    let main =
+        "# Main\n\n<!-- include -->\n\nSee [shared].\n\n[shared]: /shared\n[local]: /main-local\n";
+    let fragment = "Fragment links to [shared] and [local].\n\n[local]: /fragment-local\n";
+    let expected = r##"<h1>Main</h1>
+<p>Fragment links to <a href="/shared">shared</a> and <a href="/fragment-local">local</a>.</p>
+<p>See <a href="/shared">shared</a>.</p>
+"##;
+
+    let parser = Parser::new(main);
+    let defs = parser.reference_definitions().clone();
+    let events = parser.flat_map(|event| match event {
+        Event::Html(ref html) if html.trim() == "<!-- include -->" => Parser::new(fragment)
+            .with_reference_definitions(defs.clone())
+            .collect(),
+        event => vec![event],
+    });
+
+    let mut s = String::new();
+    html::push_html(&mut s, events);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_disable_autolinks() {
+    let original = "<http://x> <foo@example.com> <span>html</span>\n";
+    let expected = "<p>&lt;http://x&gt; &lt;foo@example.com&gt; <span>html</span></p>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::DISABLE_AUTOLINKS),
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_obfuscate_emails() {
+    let original = "<john@example.org> [mail](mailto:a@b) [web](http://x)\n";
+    let plain = "<p><a href=\"mailto:john@example.org\">john@example.org</a> \
+                 <a href=\"mailto:a@b\">mail</a> <a href=\"http://x\">web</a></p>\n";
+    let obfuscated = "<p><a href=\"&#109;&#97;&#105;&#108;&#116;&#111;&#58;\
+                      &#106;&#111;&#104;&#110;&#64;&#101;&#120;&#97;&#109;&#112;&#108;&#101;&#46;&#111;&#114;&#103;\">\
+                      &#106;&#111;&#104;&#110;&#64;&#101;&#120;&#97;&#109;&#112;&#108;&#101;&#46;&#111;&#114;&#103;</a> \
+                      <a href=\"&#109;&#97;&#105;&#108;&#116;&#111;&#58;&#97;&#64;&#98;\">&#109;&#97;&#105;&#108;</a> \
+                      <a href=\"http://x\">web</a></p>\n";
+
+    let mut s = String::new();
+    html::push_html(&mut s, Parser::new(original));
+    assert_eq!(plain, s);
+
+    let config = html::HtmlConfig {
+        obfuscate_emails: true,
+        ..Default::default()
+    };
+    s.clear();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(obfuscated, s);
+    assert!(!s.contains("john@example.org"));
+
+    // the scheme is case insensitive
+    let config = html::HtmlConfig {
+        obfuscate_emails: true,
+        ..Default::default()
+    };
+    s.clear();
+    html::push_html_ext(&mut s, Parser::new("<MailTo:a@b>"), config);
+    assert_eq!(
+        "<p><a href=\"&#77;&#97;&#105;&#108;&#84;&#111;&#58;&#97;&#64;&#98;\">\
+         &#77;&#97;&#105;&#108;&#84;&#111;&#58;&#97;&#64;&#98;</a></p>\n",
+        s
+    );
+}
+
+#[test]
+fn html_test_collapse_heading_whitespace() {
+    let original = "#  Foo   Bar  \\*\t x #\n\nFoo   Bar\n";
+    let expected = "<h1>Foo Bar * x</h1>\n<p>Foo   Bar</p>\n";
+    let config = html::HtmlConfig {
+        collapse_heading_whitespace: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_collapse_heading_whitespace_around_inlines() {
+    let original = "#  Foo   `a  b`   Bar  *baz*   <i>qux</i>   x\n";
+    let expected = "<h1>Foo <code>a  b</code> Bar <em>baz</em> <i>qux</i> x</h1>\n";
+    let config = html::HtmlConfig {
+        collapse_heading_whitespace: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_expand_tabs() {
+    let original = "a\tb `c\td`\n\n```\ne\tf\n```\n\n\tg\th\n";
+    let expected = "<p>a    b <code>c\td</code></p>\n\
+                    <pre><code>e\tf\n</code></pre>\n\
+                    <pre><code>g\th\n</code></pre>\n";
+    let config = html::HtmlConfig {
+        expand_tabs: Some(4),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_attribute_callback() {
+    let original = "[a](javascript:alert(1) \"t\") [b](http://x) ![c](javascript:y)\n\n\
+                    ```rust\n```\n";
+    let expected = "<p><a title=\"t\">a</a> <a href=\"http://x\">b</a> <img alt=\"c\" /></p>\n\
+                    <pre><code class=\"language-rust\"></code></pre>\n";
+
+    let mut seen = Vec::new();
+    let config = html::HtmlConfig {
+        attribute_callback: Some(&mut |name, value| {
+            seen.push(name);
+            if value.starts_with("javascript:") {
+                None
+            } else {
+                Some(value.into())
+            }
+        }),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+
+    use html::AttrName::*;
+    assert_eq!(vec![Href, Title, Href, Src, Class], seen);
+}
+
+#[test]
+fn html_test_sourcepos() {
+    let original = "# Title\n\
+                    \n\
+                    > quoted\n\
+                    > text\n\
+                    \n\
+                    3. one\n\
+                    4. two\n\
+                    \n\
+                    ```rust\n\
+                    code\n\
+                    ```\n\
+                    ***\n\
+                    | a |\n\
+                    |---|\n\
+                    | b |\n";
+    let expected = r##"<h1 data-sourcepos="1:1-1:7">Title</h1>
+<blockquote data-sourcepos="3:1-4:6">
+<p data-sourcepos="3:3-4:6">quoted
+text</p>
+</blockquote>
+<ol start="3" data-sourcepos="6:1-7:6">
+<li data-sourcepos="6:1-6:6">one</li>
+<li data-sourcepos="7:1-7:6">two</li>
+</ol>
+<pre data-sourcepos="9:1-11:3"><code class="language-rust">code
+</code></pre>
+<hr data-sourcepos="12:1-12:3" />
+<table data-sourcepos="13:1-15:5"><thead><tr data-sourcepos="13:1-13:5"><th>a</th></tr></thead><tbody>
+<tr data-sourcepos="15:1-15:5"><td>b</td></tr>
+</tbody></table>
+"##;
+
+    let mut s = String::new();
+    html::push_html_with_sourcepos(
+        &mut s,
+        original,
+        Parser::new_ext(original, Options::ENABLE_TABLES).into_offset_iter(),
+        Default::default(),
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_old_code_spans() {
+    let original = "` `` x `` ` `  a   b  ` `\nc\n` `  `\n\n> `d\n> e  `\n";
+    let new = "<p><code>`` x ``</code> <code> a   b </code> <code>c</code> <code>  </code></p>\n\
+               <blockquote>\n<p><code>d e  </code></p>\n</blockquote>\n";
+    let old = "<p><code>`` x ``</code> <code>a b</code> <code>c</code> <code></code></p>\n\
+               <blockquote>\n<p><code>d e</code></p>\n</blockquote>\n";
+
+    let mut s = String::new();
+    html::push_html(&mut s, Parser::new(original));
+    assert_eq!(new, s);
+
+    s.clear();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_OLD_CODE_SPANS),
+    );
+    assert_eq!(old, s);
+}
+
+#[test]
+fn html_test_code_break_after() {
+    let original = "`https://example.com/a/b.html` `x/` `<a/b>`\n\n```\nc/d\n```\n";
+    let expected = "<p><code>https:/<wbr>/<wbr>example.<wbr>com/<wbr>a/<wbr>b.<wbr>html</code> \
+                    <code>x/</code> <code>&lt;a/<wbr>b&gt;</code></p>\n\
+                    <pre><code>c/d\n</code></pre>\n";
+    let config = html::HtmlConfig {
+        code_break_after: &['/', '.'],
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_footnote_backrefs() {
+    let original = "a[^1] b[^1]\n\n[^1]: note\n";
+    let parser = || Parser::new_ext(original, Options::ENABLE_FOOTNOTES);
+
+    let mut s = String::new();
+    html::push_html(&mut s, parser());
+    assert_eq!(
+        "<p>a<sup class=\"footnote-reference\"><a href=\"#1\">1</a></sup> \
+         b<sup class=\"footnote-reference\"><a href=\"#1\">1</a></sup></p>\n\
+         <div class=\"footnote-definition\" id=\"1\"><sup class=\"footnote-definition-label\">1</sup>\n\
+         <p>note</p>\n</div>\n",
+        s
+    );
+
+    let config = html::HtmlConfig {
+        footnote_backrefs: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, parser(), config);
+    assert_eq!(
+        "<p>a<sup class=\"footnote-reference\" id=\"fnref-1\"><a href=\"#1\">1</a></sup> \
+         b<sup class=\"footnote-reference\" id=\"fnref-1-2\"><a href=\"#1\">1</a></sup></p>\n\
+         <div class=\"footnote-definition\" id=\"1\"><sup class=\"footnote-definition-label\">1</sup>\n\
+         <p>note</p>\n<a href=\"#fnref-1\" class=\"footnote-backref\">↩1</a> \
+         <a href=\"#fnref-1-2\" class=\"footnote-backref\">↩2</a>\n</div>\n",
+        s
+    );
+
+    // a single reference gets an unnumbered link
+    let config = html::HtmlConfig {
+        footnote_backrefs: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext("a[^x]\n\n[^x]: note\n", Options::ENABLE_FOOTNOTES),
+        config,
+    );
+    assert_eq!(
+        "<p>a<sup class=\"footnote-reference\" id=\"fnref-x\"><a href=\"#x\">1</a></sup></p>\n\
+         <div class=\"footnote-definition\" id=\"x\"><sup class=\"footnote-definition-label\">1</sup>\n\
+         <p>note</p>\n<a href=\"#fnref-x\" class=\"footnote-backref\">↩</a>\n</div>\n",
+        s
+    );
+
+    // unreferenced definitions and references after the definition get no
+    // links back
+    let config = html::HtmlConfig {
+        footnote_backrefs: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(
+            "[^u]: unused\n\n[^y]: first\n\nb[^y]\n",
+            Options::ENABLE_FOOTNOTES,
+        ),
+        config,
+    );
+    assert_eq!(
+        "<div class=\"footnote-definition\" id=\"u\"><sup class=\"footnote-definition-label\">1</sup>\n\
+         <p>unused</p>\n</div>\n\
+         <div class=\"footnote-definition\" id=\"y\"><sup class=\"footnote-definition-label\">2</sup>\n\
+         <p>first</p>\n</div>\n\
+         <p>b<sup class=\"footnote-reference\" id=\"fnref-y\"><a href=\"#y\">2</a></sup></p>\n",
+        s
+    );
+}
+
+#[test]
+fn html_test_task_list_ids() {
+    let original = "- [ ] a\n- b\n- [x] c\n\n1. [ ] d\n";
+    let expected = "<ul>\n\
+                    <li id=\"task-1\"><input disabled=\"\" type=\"checkbox\" data-task-id=\"task-1\"/>\na</li>\n\
+                    <li>b</li>\n\
+                    <li id=\"task-2\"><input disabled=\"\" type=\"checkbox\" data-task-id=\"task-2\" checked=\"\"/>\nc</li>\n\
+                    </ul>\n\
+                    <ol>\n\
+                    <li id=\"task-3\"><input disabled=\"\" type=\"checkbox\" data-task-id=\"task-3\"/>\nd</li>\n\
+                    </ol>\n";
+    let config = html::HtmlConfig {
+        task_list_ids: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_TASKLISTS),
+        config,
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_language_callback() {
+    let original = "```js\n```\n\n```rust extra\n```\n\n```\n```\n\n```none\n```\n";
+    let expected = "<pre><code class=\"language-javascript\"></code></pre>\n\
+                    <pre><code class=\"language-rust\"></code></pre>\n\
+                    <pre><code></code></pre>\n\
+                    <pre><code></code></pre>\n";
+
+    let mut seen = Vec::new();
+    let config = html::HtmlConfig {
+        language_callback: Some(&mut |lang| {
+            seen.push(lang.to_string());
+            match lang {
+                "js" => "javascript".into(),
+                "none" => "".into(),
+                _ => lang.into(),
+            }
+        }),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+    assert_eq!(vec!["js", "rust", "none"], seen);
+}
+
+#[test]
+fn html_test_tag_attributes() {
+    let original = "# Title\n\nsome *text* ![a](b.png)\n\n## Sub\n";
+    let expected = "<h1 itemprop=\"name\">Title</h1>\n\
+                    <p>some <em>text</em> <img src=\"b.png\" alt=\"a\" itemprop=\"image\" data-x=\"&quot;\" /></p>\n\
+                    <h2 itemprop=\"name\">Sub</h2>\n";
+    let config = html::HtmlConfig {
+        tag_attributes: Some(&mut |tag| match tag {
+            Tag::Heading(..) => vec![("itemprop".into(), "name".into())],
+            Tag::Image(..) => vec![
+                ("itemprop".into(), "image".into()),
+                ("data-x".into(), "\"".into()),
+            ],
+            _ => vec![],
+        }),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_filter_data_urls() {
+    let original = "![a](data:image/png;base64,AAAA) ![b](DATA:Image/gif,x) \
+                    ![c](data:text/html,x) [d](data:text/html,<script>) \
+                    [e](data:image/png,x) [f](http://x/data:)\n";
+    let expected = "<p><img src=\"data:image/png;base64,AAAA\" alt=\"a\" /> \
+                    <img src=\"DATA:Image/gif,x\" alt=\"b\" /> <img alt=\"c\" /> \
+                    <a>d</a> <a>e</a> <a href=\"http://x/data:\">f</a></p>\n";
+    let config = html::HtmlConfig {
+        filter_data_urls: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+
+    s.clear();
+    html::push_html(&mut s, Parser::new("[d](data:text/html,x)"));
+    assert_eq!("<p><a href=\"data:text/html,x\">d</a></p>\n", s);
+}
+
+#[test]
+fn html_test_table_colgroups() {
+    let original = "a | b | c | d\n---|:--|:-:|--:\ne | f | g | h\n";
+    let plain = r##"<table><thead><tr><th>a</th><th align="left">b</th><th align="center">c</th><th align="right">d</th></tr></thead><tbody>
+<tr><td>e</td><td align="left">f</td><td align="center">g</td><td align="right">h</td></tr>
+</tbody></table>
+"##;
+    let colgroups = r##"<table><colgroup><col /><col class="align-left" /><col class="align-center" /><col class="align-right" /></colgroup><thead><tr><th>a</th><th align="left">b</th><th align="center">c</th><th align="right">d</th></tr></thead><tbody>
+<tr><td>e</td><td align="left">f</td><td align="center">g</td><td align="right">h</td></tr>
+</tbody></table>
+"##;
+
+    let mut s = String::new();
+    html::push_html(&mut s, Parser::new_ext(original, Options::ENABLE_TABLES));
+    assert_eq!(plain, s);
+
+    let config = html::HtmlConfig {
+        table_colgroups: true,
+        ..Default::default()
+    };
+    s.clear();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_TABLES),
+        config,
+    );
+    assert_eq!(colgroups, s);
+}
+
+#[test]
+fn html_test_html_callback() {
+    let original = "<script>alert(1)</script>\n\na <b>b</b> <script>c</script>\n\n- <i>d</i>\n";
+    let mut kinds = Vec::new();
+    let config = html::HtmlConfig {
+        html_callback: Some(&mut |kind, html| {
+            kinds.push((kind, html.to_string()));
+            if html.contains("script>") {
+                "".into()
+            } else {
+                html.into()
+            }
+        }),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!("<p>a <b>b</b> c</p>\n<ul>\n<li><i>d</i></li>\n</ul>\n", s);
+
+    use html::HtmlKind::{Block, Inline};
+    let kinds: Vec<_> = kinds.iter().map(|(k, h)| (*k, h.as_str())).collect();
+    assert_eq!(
+        vec![
+            (Block, "<script>alert(1)</script>\n"),
+            (Inline, "<b>"),
+            (Inline, "</b>"),
+            (Inline, "<script>"),
+            (Inline, "</script>"),
+            (Block, "<i>"),
+            (Block, "</i>"),
+        ],
+        kinds
+    );
+}
+
+#[test]
+fn html_test_html_callback_after_figure() {
+    let original = "![a](b \"t\")\n\n<div>\nc\n</div>\n";
+    let mut kinds = Vec::new();
+    let config = html::HtmlConfig {
+        figures: true,
+        html_callback: Some(&mut |kind, html| {
+            kinds.push(kind);
+            html.into()
+        }),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(
+        "<figure><img src=\"b\" alt=\"a\" title=\"t\" /><figcaption>t</figcaption></figure>\n\
+         <div>\nc\n</div>\n",
+        s
+    );
+    assert_eq!(vec![html::HtmlKind::Block; 3], kinds);
+}
+
+#[test]
+fn html_test_wrapper() {
+    let original = "# a\n\nb[^1]\n\n[^1]: c\n";
+    let config = html::HtmlConfig {
+        wrapper: Some(("article", "markdown-body")),
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_FOOTNOTES),
+        config,
+    );
+    assert_eq!(
+        "<article class=\"markdown-body\">\n<h1>a</h1>\n\
+         <p>b<sup class=\"footnote-reference\"><a href=\"#1\">1</a></sup></p>\n\
+         <div class=\"footnote-definition\" id=\"1\"><sup class=\"footnote-definition-label\">1</sup>\n\
+         <p>c</p>\n</div>\n</article>\n",
+        s
+    );
+
+    let config = html::HtmlConfig {
+        wrapper: Some(("div", "")),
+        ..Default::default()
+    };
+    s.clear();
+    html::push_html_ext(&mut s, Parser::new(""), config);
+    assert_eq!("<div>\n</div>\n", s);
+}
+
+#[test]
+fn html_test_strip_control_chars() {
+    let original = "a\u{1}b\u{1b}[31m\tc\u{7f}\0 `\u{1}`\r\nd\n";
+    let config = html::HtmlConfig {
+        strip_control_chars: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!("<p>ab[31m\tc\u{7f}\u{FFFD} <code>\u{1}</code>\nd</p>\n", s);
+
+    s.clear();
+    html::push_html(&mut s, Parser::new(original));
+    assert_eq!(
+        "<p>a\u{1}b\u{1b}[31m\tc\u{7f}\u{FFFD} <code>\u{1}</code>\nd</p>\n",
+        s
+    );
+}
+
+#[test]
+fn html_test_heading_attributes() {
+    let original = "## Heading {#my-id .class1 .class2}\n\n\
+                    # a {#id\n\n\
+                    Setext {#x&y .\"z}\n---\n\n\
+                    # Escaped \\{#id}\n";
+    let expected = "<h2 id=\"my-id\" class=\"class1 class2\">Heading</h2>\n\
+                    <h1>a {#id</h1>\n\
+                    <h2 id=\"x&amp;y\" class=\"&quot;z\">Setext</h2>\n\
+                    <h1>Escaped {#id}</h1>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_HEADING_ATTRIBUTES),
+    );
+    assert_eq!(expected, s);
+
+    s.clear();
+    html::push_html(&mut s, Parser::new("# Heading {#my-id}\n"));
+    assert_eq!("<h1>Heading {#my-id}</h1>\n", s);
+}
+
+#[test]
+fn html_test_heading_attributes_single_line_setext() {
+    let original = "a\n{#i}\n---\n\n(\n {#j}\n-\n\nb\nc {#k .d}\n---\n";
+    let expected = "<p>a</p>\n\
+                    <h2 id=\"i\"></h2>\n\
+                    <p>(</p>\n\
+                    <h2 id=\"j\"></h2>\n\
+                    <p>b</p>\n\
+                    <h2 id=\"k\" class=\"d\">c</h2>\n";
+    let options = Options::ENABLE_SINGLE_LINE_SETEXT_HEADINGS | Options::ENABLE_HEADING_ATTRIBUTES;
+
+    let mut s = String::new();
+    html::push_html(&mut s, Parser::new_ext(original, options));
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_collected_events() {
+    let events: Vec<Event<'static>> = {
+        let original = String::from("# a\n\n*b* `c`\n");
+        Parser::new(&original).map(Event::into_static).collect()
+    };
+
+    let mut s = String::new();
+    html::push_html(&mut s, events.iter().cloned());
+    let mut bytes = Vec::new();
+    html::write_html(&mut bytes, events.iter().cloned()).unwrap();
+
+    assert_eq!("<h1>a</h1>\n<p><em>b</em> <code>c</code></p>\n", s);
+    assert_eq!(s.as_bytes(), &bytes[..]);
+}
+
+#[test]
+fn html_test_gfm_autolinks() {
+    let original = "(see www.example.com)\n\n\
+                    https://ex.com/a_(b)\n\n\
+                    Write to foo.bar@example.com.\n\n\
+                    `www.example.com` [link](https://ex.com/x_y)\n";
+    let expected = "<p>(see <a href=\"http://www.example.com\">www.example.com</a>)</p>\n\
+                    <p><a href=\"https://ex.com/a_(b)\">https://ex.com/a_(b)</a></p>\n\
+                    <p>Write to <a href=\"mailto:foo.bar@example.com\">foo.bar@example.com</a>.</p>\n\
+                    <p><code>www.example.com</code> <a href=\"https://ex.com/x_y\">link</a></p>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_GFM_AUTOLINKS),
+    );
+    assert_eq!(expected, s);
+
+    s.clear();
+    html::push_html(&mut s, Parser::new("see www.example.com\n"));
+    assert_eq!("<p>see www.example.com</p>\n", s);
+}
+
+#[test]
+fn html_test_gfm_autolinks_in_link_text() {
+    let original = "[see www.a.com](x)\n\n\
+                    [x](http://a.com) http://b.com](y)\n\n\
+                    [see a@b.com](x)\n\n\
+                    [see www.a.com][r] http://a.com/[b]\n\n\
+                    [r]: /u\n";
+    let expected = "<p><a href=\"x\">see www.a.com</a></p>\n\
+                    <p><a href=\"http://a.com\">x</a> <a href=\"http://b.com\">http://b.com</a>](y)</p>\n\
+                    <p><a href=\"x\">see a@b.com</a></p>\n\
+                    <p><a href=\"/u\">see www.a.com</a> \
+                    <a href=\"http://a.com/%5Bb%5D\">http://a.com/[b]</a></p>\n";
+
+    let mut s = String::new();
+    html::push_html(
+        &mut s,
+        Parser::new_ext(original, Options::ENABLE_GFM_AUTOLINKS),
+    );
+    assert_eq!(expected, s);
+}
+
+#[test]
+fn html_test_heading_numbers() {
+    let original = "# a\n\n## b\n\n## c\n\n### d\n\n#### e\n\n# f\n\n### g\n\n## h\n";
+    let expected = "<h1>1 a</h1>\n\
+                    <h2>1.1 b</h2>\n\
+                    <h2>1.2 c</h2>\n\
+                    <h3>1.2.1 d</h3>\n\
+                    <h4>1.2.1.1 e</h4>\n\
+                    <h1>2 f</h1>\n\
+                    <h3>2.1 g</h3>\n\
+                    <h2>2.2 h</h2>\n";
+    let config = html::HtmlConfig {
+        heading_numbers: true,
+        ..Default::default()
+    };
+    let mut s = String::new();
+    html::push_html_ext(&mut s, Parser::new(original), config);
+    assert_eq!(expected, s);
+}
@@ -75,4 +75,36 @@ mod tests {
         assert_eq!(decoded_str.as_ref(), str);
         assert_eq!(decoded_string.as_ref(), string);
     }
+
+    #[test]
+    fn ast_json_snapshot() {
+        use pulldown_cmark::{ast, Parser};
+
+        let tree = ast::from_events(Parser::new("# Title\n\n> *a* `b`\n> c\n"));
+        let expected = serde_json::json!([
+            {"tag": {"Heading": ["H1", null, []]}, "children": [{"Text": "Title"}]},
+            {"tag": "BlockQuote", "children": [
+                {"tag": "Paragraph", "children": [
+                    {"tag": "Emphasis", "children": [{"Text": "a"}]},
+                    {"Text": " "},
+                    {"Code": "b"},
+                    "SoftBreak",
+                    {"Text": "c"}
+                ]}
+            ]}
+        ]);
+
+        assert_eq!(expected, serde_json::to_value(&tree).unwrap());
+    }
+
+    #[test]
+    fn ast_json_round_trip() {
+        use pulldown_cmark::{ast, Parser};
+
+        let tree = ast::from_events(Parser::new("- [a](u \"t\")\n- ![b](v)\n"));
+        let encoded = serde_json::to_string(&tree).unwrap();
+        let decoded: Vec<ast::Node> = serde_json::from_str(&encoded).unwrap();
+
+        assert_eq!(tree, decoded);
+    }
 }
@@ -962,3 +962,1001 @@ fn regression_test_67() {
 
     test_markdown_html(original, expected, false);
 }
+
+#[test]
+fn regression_test_68() {
+    let original = r##"<http://a.b/c?d=e&f=g#h>
+
+<http://a b>
+"##;
+    let expected = r##"<p><a href="http://a.b/c?d=e&amp;f=g#h">http://a.b/c?d=e&amp;f=g#h</a></p>
+<p>&lt;http://a b&gt;</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_69() {
+    let original = r##"- a
+- b
+
+
+"##;
+    let expected = r##"<ul>
+<li>a</li>
+<li>b</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_70() {
+    let original = r##"- a
+- b
+
+  c
+"##;
+    let expected = r##"<ul>
+<li>
+<p>a</p>
+</li>
+<li>
+<p>b</p>
+<p>c</p>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_71() {
+    let original = r##"- a
+  ```
+  b
+
+
+  ```
+- c
+"##;
+    let expected = r##"<ul>
+<li>a
+<pre><code>b
+
+
+</code></pre>
+</li>
+<li>c</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_72() {
+    let original = r##"- a
+  - b
+  - c
+
+- d
+"##;
+    let expected = r##"<ul>
+<li>
+<p>a</p>
+<ul>
+<li>b</li>
+<li>c</li>
+</ul>
+</li>
+<li>
+<p>d</p>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_73() {
+    let original = r##"- a
+-
+
+- c
+"##;
+    let expected = r##"<ul>
+<li>
+<p>a</p>
+</li>
+<li></li>
+<li>
+<p>c</p>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_74() {
+    let original = r##"> - a
+>
+> - b
+"##;
+    let expected = r##"<blockquote>
+<ul>
+<li>
+<p>a</p>
+</li>
+<li>
+<p>b</p>
+</li>
+</ul>
+</blockquote>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_75() {
+    let original = r##"[a]( url )
+
+[a](
+url
+)
+"##;
+    let expected = r##"<p><a href="url">a</a></p>
+<p><a href="url">a</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_76() {
+    let original = r##"[a](url "t")
+
+[a](<url> "t")
+
+[a](  <u rl>	't'  )
+
+[a](url title)
+"##;
+    let expected = r##"<p><a href="url" title="t">a</a></p>
+<p><a href="url" title="t">a</a></p>
+<p><a href="u%20rl" title="t">a</a></p>
+<p>[a](url title)</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_77() {
+    let original = r##"[a](<url>"t")
+
+[a](url"t")
+"##;
+    let expected = r##"<p>[a](<url>&quot;t&quot;)</p>
+<p><a href="url%22t%22">a</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_78() {
+    let original = r##"[ foo ]: /foo
+[bar]: /bar
+
+[foo] [ bar ]
+
+[x][foo ] [y][
+bar]
+
+[ Foo ][]
+"##;
+    let expected = r##"<p><a href="/foo">foo</a> <a href="/bar"> bar </a></p>
+<p><a href="/foo">x</a> <a href="/bar">y</a></p>
+<p><a href="/foo"> Foo </a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_79() {
+    let original = r##"[a [b] c](url)
+
+[a [[b]] c](url)
+
+[a [b] c][r]
+
+[r]: /r
+"##;
+    let expected = r##"<p><a href="url">a [b] c</a></p>
+<p><a href="url">a [[b]] c</a></p>
+<p><a href="/r">a [b] c</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_80() {
+    let original = r##"[a [b](y) c](url)
+
+[a [b][r] c](url)
+
+[a ![b](y) c](url)
+
+![a [b](y) c](url)
+
+[r]: /r
+"##;
+    let expected = r##"<p>[a <a href="y">b</a> c](url)</p>
+<p>[a <a href="/r">b</a> c](url)</p>
+<p><a href="url">a <img src="y" alt="b" /> c</a></p>
+<p><img src="url" alt="a b c" /></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_81() {
+    let original = r##"- [ ] a
+- [x] b
+-  [X] c
+1. [ ] d
+"##;
+    let expected = r##"<ul>
+<li><input disabled="" type="checkbox"/>
+a</li>
+<li><input disabled="" type="checkbox" checked=""/>
+b</li>
+<li><input disabled="" type="checkbox" checked=""/>
+c</li>
+</ul>
+<ol>
+<li><input disabled="" type="checkbox"/>
+d</li>
+</ol>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_82() {
+    let original = r##"- x [ ] a
+- \[ ] b
+- c
+  [ ] d
+- *[ ]* e
+- [ ] [x] f
+"##;
+    let expected = r##"<ul>
+<li>x [ ] a</li>
+<li>[ ] b</li>
+<li>c
+[ ] d</li>
+<li><em>[ ]</em> e</li>
+<li><input disabled="" type="checkbox"/>
+[x] f</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_83() {
+    let original = r##"- [ ]a
+- [  ] b
+- [y] c
+- > [ ] d
+"##;
+    let expected = r##"<ul>
+<li>[ ]a</li>
+<li>[  ] b</li>
+<li>[y] c</li>
+<li>
+<blockquote>
+<p>[ ] d</p>
+</blockquote>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_84() {
+    let original = r##"a  
+b\
+*c*
+===
+
+d\
+---
+
+e  
+===
+"##;
+    let expected = r##"<h1>a<br />
+b<br />
+<em>c</em></h1>
+<h2>d\</h2>
+<h1>e</h1>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_85() {
+    let original = r##"***
+* * *
+*	*	*
+***text***
+***text
+*** text
+text
+***
+"##;
+    let expected = r##"<hr />
+<hr />
+<hr />
+<p><em><strong>text</strong></em>
+***text
+*** text
+text</p>
+<hr />
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_86() {
+    let original = r##"[foo]: <a
+b>
+
+[foo]
+"##;
+    let expected = r##"<p>[foo]: <a
+b></p>
+<p>[foo]</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_87() {
+    let original = r##"[foo]:
+<a b>
+
+[bar]: <c\>d>
+
+[foo] [bar]
+"##;
+    let expected = r##"<p><a href="a%20b">foo</a> <a href="c%3Ed">bar</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_88() {
+    let original = r##"[foo]: a
+b
+
+[bar]: c d
+
+[foo] [bar]
+"##;
+    let expected = r##"<p>b</p>
+<p>[bar]: c d</p>
+<p><a href="a">foo</a> [bar]</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_89() {
+    let original = r##"<http://a.com/foo_bar_baz>
+
+<http://a.com/_bar_> <http://a.com/foo__bar__baz>
+
+_<http://a.com/_x>_
+"##;
+    let expected = r##"<p><a href="http://a.com/foo_bar_baz">http://a.com/foo_bar_baz</a></p>
+<p><a href="http://a.com/_bar_">http://a.com/_bar_</a> <a href="http://a.com/foo__bar__baz">http://a.com/foo__bar__baz</a></p>
+<p><em><a href="http://a.com/_x">http://a.com/_x</a></em></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_90() {
+    let original = r##"\&amp; \& &amp;
+
+\&#35; \&copy; &#35;
+
+\\&amp; [a](\&amp;)
+"##;
+    let expected = r##"<p>&amp;amp; &amp; &amp;</p>
+<p>&amp;#35; &amp;copy; #</p>
+<p>\&amp; <a href="&amp;amp;">a</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_91() {
+    let original = r##"foo\
+"##;
+    let expected = r##"<p>foo\</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_92() {
+    let original = r##"foo\
+
+bar\
+# baz
+"##;
+    let expected = r##"<p>foo\</p>
+<p>bar\</p>
+<h1>baz</h1>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_93() {
+    let original = r##"1.  a
+
+        code
+    b
+
+  c
+"##;
+    let expected = r##"<ol>
+<li>
+<p>a</p>
+<pre><code>code
+</code></pre>
+<p>b</p>
+</li>
+</ol>
+<p>c</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_94() {
+    let original = r##"- a
+
+	code
+
+- b
+
+		code
+"##;
+    let expected = r##"<ul>
+<li>
+<p>a</p>
+<p>code</p>
+</li>
+<li>
+<p>b</p>
+<pre><code>  code
+</code></pre>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_95() {
+    let original = r##"[foo]: /url "a
+b"
+
+[bar]: /url "a \" b"
+
+[foo] [bar]
+"##;
+    let expected = r##"<p><a href="/url" title="a
+b">foo</a> <a href="/url" title="a &quot; b">bar</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_96() {
+    let original = r##"> [foo]: /url 'a
+> b \' c'
+>
+> [foo]
+
+- [bar]: /url (a
+  b)
+
+  [bar]
+"##;
+    let expected = r##"<blockquote>
+<p><a href="/url" title="a
+b ' c">foo</a></p>
+</blockquote>
+<ul>
+<li><a href="/url" title="a
+b">bar</a></li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_97() {
+    let original = r##"> [foo]: /url "a
+>
+> b"
+>
+> [foo]
+"##;
+    let expected = r##"<blockquote>
+<p>[foo]: /url &quot;a</p>
+<p>b&quot;</p>
+<p>[foo]</p>
+</blockquote>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_98() {
+    let original = r##"- foo
+
+  bar
+
+-  foo
+
+  bar
+
+-  foo
+
+   bar
+"##;
+    let expected = r##"<ul>
+<li>
+<p>foo</p>
+<p>bar</p>
+</li>
+<li>
+<p>foo</p>
+</li>
+</ul>
+<p>bar</p>
+<ul>
+<li>
+<p>foo</p>
+<p>bar</p>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_99() {
+    let original = r##"-     foo
+
+  bar
+"##;
+    let expected = r##"<ul>
+<li>
+<pre><code>foo
+</code></pre>
+<p>bar</p>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_100() {
+    let original = r##"1.      code
+
+2. b
+"##;
+    let expected = r##"<ol>
+<li>
+<pre><code> code
+</code></pre>
+</li>
+<li>
+<p>b</p>
+</li>
+</ol>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_101() {
+    let original = r##"[foo]: /url
+"title"
+
+[bar]: /url
+"title" ok
+
+[baz]: /url
+'title
+
+[foo] [bar] [baz]
+"##;
+    let expected = r##"<p>&quot;title&quot; ok</p>
+<p>'title</p>
+<p><a href="/url" title="title">foo</a> <a href="/url">bar</a> <a href="/url">baz</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_102() {
+    let original = r##"*a`b*`c
+
+`*foo`*
+
+*a `*` b*
+
+_foo`_`bar_
+
+**a`**`**
+
+*`a*` b*
+
+`a *b` c*
+"##;
+    let expected = r##"<p>*a<code>b*</code>c</p>
+<p><code>*foo</code>*</p>
+<p><em>a <code>*</code> b</em></p>
+<p><em>foo<code>_</code>bar</em></p>
+<p><strong>a<code>**</code></strong></p>
+<p><em><code>a*</code> b</em></p>
+<p><code>a *b</code> c*</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_103() {
+    let original = r##"<HTTP://x> <MailTo:a@b> <hTTps://X.y/Z>
+"##;
+    let expected = r##"<p><a href="HTTP://x">HTTP://x</a> <a href="MailTo:a@b">MailTo:a@b</a> <a href="hTTps://X.y/Z">hTTps://X.y/Z</a></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_104() {
+    let original = r##"> a
+>
+> b
+
+> c
+>
+d
+
+> e
+>
+> > f
+"##;
+    let expected = r##"<blockquote>
+<p>a</p>
+<p>b</p>
+</blockquote>
+<blockquote>
+<p>c</p>
+</blockquote>
+<p>d</p>
+<blockquote>
+<p>e</p>
+<blockquote>
+<p>f</p>
+</blockquote>
+</blockquote>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_105() {
+    let original = r##"> ```
+> a
+>
+> b
+"##;
+    let expected = r##"<blockquote>
+<pre><code>a
+
+b
+</code></pre>
+</blockquote>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_106() {
+    let original = r##"- ```
+  a
+
+  b
+"##;
+    let expected = r##"<ul>
+<li>
+<pre><code>a
+
+b
+</code></pre>
+</li>
+</ul>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_107() {
+    let original = r##"a `code`
+b
+`code` c
+
+`a
+b`
+c
+
+a `b
+` c
+"##;
+    let expected = r##"<p>a <code>code</code>
+b
+<code>code</code> c</p>
+<p><code>a b</code>
+c</p>
+<p>a <code>b </code> c</p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_108() {
+    let original = r##"a | b
+--|--
+c\
+d | e
+f<br>g | h\
+"##;
+    let expected = r##"<table><thead><tr><th>a</th><th>b</th></tr></thead><tbody>
+<tr><td>c\</td><td></td></tr>
+<tr><td>d</td><td>e</td></tr>
+<tr><td>f<br>g</td><td>h\</td></tr>
+</tbody></table>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_109() {
+    let original = r##"5. a
+6. b
+
+0) c
+
+003. d
+"##;
+    let expected = r##"<ol start="5">
+<li>a</li>
+<li>b</li>
+</ol>
+<ol start="0">
+<li>c</li>
+</ol>
+<ol start="3">
+<li>d</li>
+</ol>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_110() {
+    let original = r##"    a
+		
+      
+    b
+	
+		
+
+```
+c
+	
+
+	
+```
+"##;
+    let expected = r##"<pre><code>a
+	
+  
+b
+</code></pre>
+<pre><code>c
+	
+
+	
+</code></pre>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_111() {
+    let original = r##"```a`b
+x
+
+~~~a`b
+x
+~~~
+
+~~~ rust `y`
+z
+~~~
+"##;
+    let expected = r##"<p>```a`b
+x</p>
+<pre><code class="language-a`b">x
+</code></pre>
+<pre><code class="language-rust">z
+</code></pre>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_112() {
+    let original = r##"a
+<div>
+b
+
+c
+<!-- d -->
+e
+
+f
+<custom>
+g
+
+h
+</custom>
+
+<custom>
+i
+"##;
+    let expected = r##"<p>a</p>
+<div>
+b
+<p>c</p>
+<!-- d -->
+<p>e</p>
+<p>f
+<custom>
+g</p>
+<p>h
+</custom></p>
+<custom>
+i
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_113() {
+    let original = r##">>a
+
+> > b
+
+>> c
+> d
+
+>>> e
+"##;
+    let expected = r##"<blockquote>
+<blockquote>
+<p>a</p>
+</blockquote>
+</blockquote>
+<blockquote>
+<blockquote>
+<p>b</p>
+</blockquote>
+</blockquote>
+<blockquote>
+<blockquote>
+<p>c
+d</p>
+</blockquote>
+</blockquote>
+<blockquote>
+<blockquote>
+<blockquote>
+<p>e</p>
+</blockquote>
+</blockquote>
+</blockquote>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn regression_test_114() {
+    let original = r##"كلمة_مائلة_هنا
+
+_كلمة_ مائلة
+
+é_a_ and _é_
+
+الجملة؝_مائلة_ و؛_x_
+
+Привет_мир_ and _мир_
+"##;
+    let expected = r##"<p>كلمة_مائلة_هنا</p>
+<p><em>كلمة</em> مائلة</p>
+<p>é_a_ and <em>é</em></p>
+<p>الجملة؝<em>مائلة</em> و؛<em>x</em></p>
+<p>Привет_мир_ and <em>мир</em></p>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
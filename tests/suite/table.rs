@@ -203,3 +203,83 @@ fn table_test_12() {
 
     test_markdown_html(original, expected, false);
 }
+
+#[test]
+fn table_test_13() {
+    let original = r##"| a | b |
+|---|---|
+| c | d |
+c | d
+"##;
+    let expected = r##"<table><thead><tr><th>a</th><th>b</th></tr></thead>
+<tr><td>c</td><td>d</td></tr>
+<tr><td>c</td><td>d</td></tr>
+</table>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn table_test_14() {
+    let original = r##"a | b
+---|---
+| c | d |
+c | d
+"##;
+    let expected = r##"<table><thead><tr><th>a</th><th>b</th></tr></thead>
+<tr><td>c</td><td>d</td></tr>
+<tr><td>c</td><td>d</td></tr>
+</table>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn table_test_15() {
+    let original = r##"| a | b |
+---|---
+| c | d
+c | d |
+"##;
+    let expected = r##"<table><thead><tr><th>a</th><th>b</th></tr></thead>
+<tr><td>c</td><td>d</td></tr>
+<tr><td>c</td><td>d</td></tr>
+</table>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn table_test_16() {
+    let original = r##"a | b
+|---|---|
+| c | d |
+c | d
+"##;
+    let expected = r##"<table><thead><tr><th>a</th><th>b</th></tr></thead>
+<tr><td>c</td><td>d</td></tr>
+<tr><td>c</td><td>d</td></tr>
+</table>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
+
+#[test]
+fn table_test_17() {
+    let original = r##"| a |
+-|
+b
+| c
+"##;
+    let expected = r##"<table><thead><tr><th>a</th></tr></thead>
+<tr><td>b</td></tr>
+<tr><td>c</td></tr>
+</table>
+"##;
+
+    test_markdown_html(original, expected, false);
+}
@@ -35,14 +35,14 @@ def main(args):
     ascii_set = set((ord(c) for c in ascii_punct))
 
     punct = set()
-    for line in file(args[1]):
+    for line in open(args[1]):
         spl = line.split(';')
         if spl[2] in ('Pc', 'Pd', 'Pe', 'Pf', 'Pi', 'Po', 'Ps'):
             punct.add(int(spl[0], 16))
     pshift = list(set((cp // 16 for cp in punct if cp >= 128)))
     pshift.sort()
     bits = [get_bits(high, punct) for high in pshift]
-    print """// Copyright 2015 Google Inc. All rights reserved.
+    print("""// Copyright 2015 Google Inc. All rights reserved.
 //
 // Permission is hereby granted, free of charge, to any person obtaining a copy
 // of this software and associated documentation files (the "Software"), to deal
@@ -66,22 +66,22 @@ def main(args):
 
 // Autogenerated by mk_puncttable.py
 
-const PUNCT_MASKS_ASCII: [u16; 8] = ["""
+const PUNCT_MASKS_ASCII: [u16; 8] = [""")
     for x in range(8):
         y = get_bits(x, ascii_set)
-        print '    0x%04x,  // U+%04X...U+%04X' % (y, x * 16, x * 16 + 15)
-    print """];
+        print('    0x%04x,  // U+%04X...U+%04X' % (y, x * 16, x * 16 + 15))
+    print("""];
 
-const PUNCT_TAB: [u16; %i] = [""" % len(pshift)
+const PUNCT_TAB: [u16; %i] = [""" % len(pshift))
     for x in pshift:
-        print '    %d,  // U+%04X...U+%04X' % (x, x * 16, x * 16 + 15)
-    print """];
+        print('    %d,  // U+%04X...U+%04X' % (x, x * 16, x * 16 + 15))
+    print("""];
 
-const PUNCT_MASKS: [u16; %i] = [""" % len(pshift)
+const PUNCT_MASKS: [u16; %i] = [""" % len(pshift))
     for i, y in enumerate(bits):
         x = pshift[i]
-        print '    0x%04x,  // U+%04X...U+%04X' % (y, x * 16, x * 16 + 15)
-    print """];
+        print('    0x%04x,  // U+%04X...U+%04X' % (y, x * 16, x * 16 + 15))
+    print(r"""];
 
 pub(crate) fn is_ascii_punctuation(c: u8) -> bool {
     c < 128 && (PUNCT_MASKS_ASCII[(c / 16) as usize] & (1 << (c & 15))) != 0
@@ -120,11 +120,14 @@ mod tests {
 
         assert!(is_punctuation('\u{00A1}'));
         assert!(is_punctuation('\u{060C}'));
+        assert!(is_punctuation('\u{061D}'));
         assert!(is_punctuation('\u{FF65}'));
         assert!(is_punctuation('\u{1BC9F}'));
         assert!(!is_punctuation('\u{1BCA0}'));
+        assert!(is_punctuation('\u{1E95F}'));
+        assert!(!is_punctuation('\u{1E960}'));
     }
 }
-""" % max(punct)
+""" % max(punct))
 
 main(sys.argv)